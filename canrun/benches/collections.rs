@@ -1,5 +1,6 @@
 use canrun::example::Collections;
 use canrun::lmap;
+use canrun::lvec::{self, lvec};
 use canrun::{both, unify, var, Goal};
 use criterion::{BenchmarkId, Criterion, Throughput};
 use std::ops::Range;
@@ -84,3 +85,43 @@ pub fn unify_lmaps(c: &mut Criterion) {
     }
     group.finish();
 }
+
+pub fn duplicate_members(c: &mut Criterion) {
+    let mut group = c.benchmark_group("duplicate_members");
+    group.sample_size(30);
+    for size in (2..MAX_EXP).map(|n| BASE.pow(n)) {
+        group.throughput(Throughput::Elements(size as u64));
+
+        group.bench_with_input(
+            BenchmarkId::new("duplicated/.collect()", size),
+            &size,
+            |bench, size| {
+                bench.iter(|| {
+                    let x = var();
+                    let mut goals: Vec<Goal<Collections>> =
+                        (0..*size).map(|_| lvec::member(1, x)).collect();
+                    goals.push(unify(x, lvec![1, 1, 2]));
+                    let results: Vec<_> = Goal::all(goals).query(x).collect();
+                    results
+                });
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("distinct/.collect()", size),
+            &size,
+            |bench, size| {
+                bench.iter(|| {
+                    let x = var();
+                    let mut goals: Vec<Goal<Collections>> =
+                        (0..*size).map(|n| lvec::member(n, x)).collect();
+                    let items: lvec::LVec<i32> = (0..*size).into();
+                    goals.push(unify(x, items));
+                    let results: Vec<_> = Goal::all(goals).query(x).collect();
+                    results
+                });
+            },
+        );
+    }
+    group.finish();
+}
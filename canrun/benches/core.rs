@@ -110,6 +110,36 @@ pub fn benches(c: &mut Criterion) {
         }
     }}
 
+    goal_bench! {c "unify_a_var_chain" (a, k) {
+        {
+            let (b, c, d, e, f, g, h, i, j) =
+                (var(), var(), var(), var(), var(), var(), var(), var(), var());
+            all![
+                unify(a, b),
+                unify(b, c),
+                unify(c, d),
+                unify(d, e),
+                unify(e, f),
+                unify(f, g),
+                unify(g, h),
+                unify(h, i),
+                unify(i, j),
+                unify(j, k),
+                unify(a, 1),
+                unify(k, 1),
+            ] as Goal<I32>
+        }
+    }}
+
+    goal_bench! {c "unify_a_long_var_chain" (k) {
+        {
+            let vars: Vec<_> = (0..100).map(|_| var()).collect();
+            let chain = vars.windows(2).map(|pair| unify(pair[0], pair[1]));
+            let lookups = (0..10).map(|_| unify(vars[0], k));
+            Goal::all(chain.chain(lookups).chain(std::iter::once(unify(k, 1)))) as Goal<I32>
+        }
+    }}
+
     goal_bench! {c "unify_a_repetitive" (k) {
         {
             let (a, b, c, d, e, f, g, h, i, j) =
@@ -2,5 +2,10 @@ use criterion::{criterion_group, criterion_main};
 mod collections;
 mod core;
 
-criterion_group!(benches, core::benches, collections::unify_lmaps);
+criterion_group!(
+    benches,
+    core::benches,
+    collections::unify_lmaps,
+    collections::duplicate_members
+);
 criterion_main!(benches);
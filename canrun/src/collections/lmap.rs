@@ -1,12 +1,15 @@
 //! A [`HashMap`](std::collections::HashMap)-like data structure with
 //! [`LVar`](crate::value::LVar) keys and values.
-use crate::state::{Fork, StateIter};
-use crate::{DomainType, IntoVal, ReifyIn, ResolvedState, State, UnifyIn, Val};
+use crate::shared::Rc;
+use crate::state::{Fork, OccursCheck, StateIter};
+use crate::{
+    DomainType, FreshVars, IntoVal, Reified, ReifyFresh, ReifyIn, ResolvedState, State, UnifyIn,
+    Val,
+};
 use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Debug;
 use std::hash::Hash;
-use std::rc::Rc;
 
 mod compare;
 
@@ -15,10 +18,53 @@ pub use compare::{subset, superset};
 /// A [`HashMap`](std::collections::HashMap)-like data structure with
 /// [`LVar`](crate::value::LVar) keys and values.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "K: serde::Serialize, V: serde::Serialize",
+        deserialize = "K: serde::Deserialize<'de>, V: serde::Deserialize<'de>"
+    ))
+)]
 pub struct LMap<K: Eq + Hash + Debug, V: Debug> {
+    // Keys may be variables, which formats such as JSON can't use as map keys.
+    #[cfg_attr(feature = "serde", serde(with = "pairs"))]
     map: HashMap<Val<K>, Val<V>>,
 }
 
+#[cfg(feature = "serde")]
+mod pairs {
+    use crate::Val;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::HashMap;
+    use std::fmt::Debug;
+    use std::hash::Hash;
+
+    pub(super) fn serialize<K, V, S>(
+        map: &HashMap<Val<K>, Val<V>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        K: Eq + Hash + Debug + Serialize,
+        V: Debug + Serialize,
+        S: Serializer,
+    {
+        serializer.collect_seq(map.iter())
+    }
+
+    pub(super) fn deserialize<'de, K, V, D>(
+        deserializer: D,
+    ) -> Result<HashMap<Val<K>, Val<V>>, D::Error>
+    where
+        K: Eq + Hash + Debug + Deserialize<'de>,
+        V: Debug + Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let pairs: Vec<(Val<K>, Val<V>)> = Vec::deserialize(deserializer)?;
+        Ok(pairs.into_iter().collect())
+    }
+}
+
 impl<K: Eq + Hash + Debug, V: Debug> LMap<K, V> {
     /// Create a new [`LMap`] value.
     ///
@@ -88,6 +134,13 @@ where
         let state = unify_entries(state, b, a)?;
         Some(state)
     }
+
+    fn occurs_check(&self, check: &mut OccursCheck<'_, 'a, D>) {
+        for (key, value) in self.map.iter() {
+            check.val(key);
+            check.val(value);
+        }
+    }
 }
 
 fn unify_entries<'a, K, V, D>(
@@ -145,6 +198,10 @@ where
                 .unify(&a_value, &b_value)
         }))
     }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.b_map.map.len())
+    }
 }
 
 impl<'a, D, Kv: Eq + Hash + Debug, Kr, Vv: Debug, Vr> ReifyIn<'a, D> for LMap<Kv, Vv>
@@ -167,6 +224,22 @@ where
     }
 }
 
+impl<'a, D, Kv: Eq + Hash + Debug, Kr, Vv: Debug, Vr> ReifyFresh<'a, D> for LMap<Kv, Vv>
+where
+    D: DomainType<'a, Kv> + DomainType<'a, Vv> + 'a,
+    Kv: ReifyFresh<'a, D, Reified = Kr>,
+    Kr: Eq + Hash,
+    Vv: ReifyFresh<'a, D, Reified = Vr>,
+{
+    type Reified = HashMap<Reified<Kr>, Reified<Vr>>;
+    fn reify_fresh(&self, state: &ResolvedState<D>, fresh: &mut FreshVars) -> Self::Reified {
+        self.map
+            .iter()
+            .map(|(k, v)| (k.reify_fresh(state, fresh), v.reify_fresh(state, fresh)))
+            .collect()
+    }
+}
+
 /// Create an [`LMap`](crate::lmap::LMap) with automatic key/value [`IntoVal`
 /// wrapping](crate::value::IntoVal).
 ///
@@ -298,4 +371,15 @@ mod tests {
             vec![unify(m, lmap!(x => 1, 1 => 2)), unify(m, lmap!(1 => 2))];
         util::assert_permutations_resolve_to(goals, (m, x), vec![]);
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip_keeps_vars() {
+        let x = var::<i32>();
+        let map: lmap::LMap<i32, i32> = lmap! {1 => x};
+        let json = serde_json::to_string(&map).unwrap();
+        let restored: lmap::LMap<i32, i32> = serde_json::from_str(&json).unwrap();
+        let goal: Goal<Collections> = unify(restored, lmap! {1 => 2});
+        assert_eq!(goal.query(x).collect::<Vec<_>>(), vec![2]);
+    }
 }
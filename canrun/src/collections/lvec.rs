@@ -6,12 +6,17 @@ mod subset;
 pub use member::member;
 pub use subset::subset;
 
-use crate::{DomainType, IntoVal, ReifyIn, ResolvedState, State, UnifyIn, Val};
+use crate::shared::Rc;
+use crate::state::OccursCheck;
+use crate::{
+    DomainType, FreshVars, IntoVal, Reified, ReifyFresh, ReifyIn, ResolvedState, State, UnifyIn,
+    Val,
+};
 use std::fmt::Debug;
-use std::rc::Rc;
 
 /// A [`Vec`]-like data structure with [`LVar`](crate::value::LVar) values.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LVec<T: Debug> {
     vec: Vec<Val<T>>,
 }
@@ -103,6 +108,10 @@ where
             None
         }
     }
+
+    fn occurs_check(&self, check: &mut OccursCheck<'_, 'a, D>) {
+        self.vec.iter().for_each(|v| check.val(v));
+    }
 }
 
 impl<'a, D, T> ReifyIn<'a, D> for LVec<T>
@@ -116,6 +125,20 @@ where
     }
 }
 
+impl<'a, D, T> ReifyFresh<'a, D> for LVec<T>
+where
+    T: ReifyFresh<'a, D> + Debug,
+    D: DomainType<'a, T> + 'a,
+{
+    type Reified = Vec<Reified<T::Reified>>;
+    fn reify_fresh(&self, state: &ResolvedState<D>, fresh: &mut FreshVars) -> Self::Reified {
+        self.vec
+            .iter()
+            .map(|v: &Val<T>| v.reify_fresh(state, fresh))
+            .collect()
+    }
+}
+
 impl<'a, T, I, IV> From<I> for LVec<T>
 where
     T: Debug,
@@ -132,7 +155,9 @@ where
 #[cfg(test)]
 mod tests {
     use crate::example::Collections;
-    use crate::{unify, util, val, var, Goal, IterResolved, ReifyIn, ResolvedState, State};
+    use crate::{
+        unify, util, val, var, Goal, IterResolved, Reified, ReifyIn, ResolvedState, State,
+    };
 
     #[test]
     fn succeeds() {
@@ -165,4 +190,30 @@ mod tests {
                 assert_eq!(x.reify_in(&state), Some(vec![1, 2]));
             });
     }
+
+    #[test]
+    fn query_fresh_elements() {
+        let (x, y) = (var(), var());
+        let goal: Goal<Collections> = unify(x, lvec![y, 2, y]);
+        let results: Vec<_> = goal.query_fresh(x).collect();
+        assert_eq!(
+            results,
+            vec![Reified::Value(vec![
+                Reified::Fresh(0),
+                Reified::Value(2),
+                Reified::Fresh(0)
+            ])]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip_keeps_vars() {
+        let x = var::<i32>();
+        let list: crate::lvec::LVec<i32> = lvec![1, x];
+        let json = serde_json::to_string(&list).unwrap();
+        let restored: crate::lvec::LVec<i32> = serde_json::from_str(&json).unwrap();
+        let goal: Goal<Collections> = unify(restored, lvec![1, 2]);
+        assert_eq!(goal.query(x).collect::<Vec<_>>(), vec![2]);
+    }
 }
@@ -1,7 +1,7 @@
 use crate::goals::{unify, Goal};
 use crate::lvec::LVec;
 use crate::state::{
-    constraints::{resolve_1, Constraint, ResolveFn, VarWatch},
+    constraints::{resolve_1, Constraint, ConstraintKey, ResolveFn, VarWatch},
     State,
 };
 use crate::value::{IntoVal, Val};
@@ -75,6 +75,14 @@ where
             .collect();
         Ok(Box::new(|state| Goal::any(goals).apply(state)))
     }
+
+    fn key(&self) -> Option<ConstraintKey<'a, D>> {
+        Some(
+            ConstraintKey::new("Member")
+                .with(&self.item)
+                .with(&self.collection),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -84,6 +92,7 @@ mod tests {
     use crate::lvec;
     use crate::util;
     use crate::value::var;
+    use crate::State;
 
     #[test]
     fn basic_member() {
@@ -147,4 +156,24 @@ mod tests {
 
         util::assert_permutations_resolve_to(goals, x, vec![]);
     }
+
+    #[test]
+    fn duplicates_are_only_kept_once() {
+        let (x, y) = (var(), var());
+        let goal: Goal<Collections> = Goal::all(vec![
+            lvec::member(1, x),
+            lvec::member(1, x),
+            lvec::member(2, x),
+            lvec::member(y, x),
+            unify(y, 1),
+            lvec::member(1, x),
+        ]);
+        let state = goal.clone().apply(State::new()).unwrap();
+        // `member(y, x)` stays, since `y` was unbound when it was added.
+        assert_eq!(state.pending_constraints().len(), 3);
+
+        // Each remaining `1` constraint can match either of the first two items.
+        let goal = Goal::all(vec![goal, unify(x, lvec![1, 1, 2])]);
+        assert_eq!(goal.count_solutions(), 4);
+    }
 }
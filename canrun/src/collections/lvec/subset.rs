@@ -1,10 +1,10 @@
 use crate::goals::{unify, Goal};
 use crate::lvec::LVec;
 use crate::state::{
-    constraints::{resolve_2, Constraint, ResolveFn, VarWatch},
+    constraints::{resolve_2, Constraint, ConstraintKey, ResolveFn, VarWatch},
     State,
 };
-use crate::value::{val, IntoVal, Val};
+use crate::value::{val, IntoVal, Val, Val::Resolved};
 use crate::{DomainType, UnifyIn};
 use std::fmt::Debug;
 use std::iter::repeat;
@@ -74,6 +74,24 @@ where
             Ok(Box::new(|state| Goal::any(goals).apply(state)))
         }
     }
+
+    fn key(&self) -> Option<ConstraintKey<'a, D>> {
+        Some(
+            ConstraintKey::new("Subset")
+                .with(&self.subset)
+                .with(&self.collection),
+        )
+    }
+
+    // A single item subset is the same as a member of the collection.
+    fn subsumes(&self, state: &State<'a, D>) -> Vec<ConstraintKey<'a, D>> {
+        match state.resolve_val(&self.subset) {
+            Resolved(subset) if subset.len() == 1 => vec![ConstraintKey::new("Member")
+                .with(&subset.vec[0])
+                .with(&self.collection)],
+            _ => Vec::new(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -83,6 +101,7 @@ mod tests {
     use crate::lvec;
     use crate::util;
     use crate::value::var;
+    use crate::State;
 
     #[test]
     fn basic_subset() {
@@ -147,4 +166,19 @@ mod tests {
 
         util::assert_permutations_resolve_to(goals, x, vec![]);
     }
+
+    #[test]
+    fn single_item_subsets_subsume_members() {
+        let x = var();
+        let goal: Goal<Collections> = Goal::all(vec![
+            lvec::subset(lvec![1], x),
+            lvec::member(1, x),
+            lvec::member(2, x),
+        ]);
+        let state = goal.clone().apply(State::new()).unwrap();
+        assert_eq!(state.pending_constraints().len(), 2);
+
+        let goal = Goal::all(vec![goal, unify(x, lvec![1, 2, 1])]);
+        assert_eq!(goal.count_solutions(), 2);
+    }
 }
@@ -1,9 +1,9 @@
 //! Constrain the set of types that you can reason about in a particular
 //! context.
 
+use crate::shared::{HashMap, Shareable};
 use crate::state::State;
 use crate::value::{LVar, Val};
-use im_rc::HashMap;
 use std::fmt::Debug;
 
 /// Manage values for a set of specific types.
@@ -31,12 +31,12 @@ use std::fmt::Debug;
 /// let goal: Goal<MyDomain> = unify(x, 1);
 /// # }
 /// ```
-pub trait Domain<'a>: Clone + Debug {
+pub trait Domain<'a>: Clone + Debug + Shareable {
     /// An individual value that may contain any of the valid types in this
     /// domain.
     ///
     /// Typically for internal use.
-    type Value: Debug + Clone + 'a;
+    type Value: Debug + Clone + Shareable + 'a;
 
     /// Create a new, valid domain.
     ///
@@ -55,24 +55,122 @@ pub trait Domain<'a>: Clone + Debug {
         a: Self::Value,
         b: Self::Value,
     ) -> Option<State<'a, Self>>;
+
+    /// List every bound variable along with the value it is bound to, as
+    /// [domain level values](crate::domains::Domain::Value).
+    ///
+    /// Typically for internal use.
+    fn bindings(&self) -> Vec<(Self::Value, Self::Value)>;
 }
 
 /// A type specific container used by a [`Domain`](crate::domains::Domain) to
 /// hold values.
 ///
+/// Bindings are kept in a persistent union-find. Each bound variable points
+/// either at another variable or at a resolved value. Chains of variables are
+/// shortened as they are followed, and when two unbound variables are joined
+/// the one with the shallower tree is pointed at the other. Both maps are
+/// persistent, so cloning a [`State`](crate::state::State) at a fork stays
+/// cheap.
+///
 /// Created by the `domain!` macro and intended for internal use.
 #[derive(Debug)]
-pub struct DomainValues<T: Debug>(pub(crate) HashMap<LVar<T>, Val<T>>);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DomainValues<T: Debug> {
+    bindings: HashMap<LVar<T>, Val<T>>,
+    // Only allocated once two unbound variables are joined.
+    ranks: Option<HashMap<LVar<T>, usize>>,
+}
 
 impl<T: Debug> DomainValues<T> {
     #[doc(hidden)]
     pub fn new() -> Self {
-        DomainValues(HashMap::new())
+        DomainValues {
+            bindings: HashMap::new(),
+            ranks: None,
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn iter(&self) -> impl Iterator<Item = (&LVar<T>, &Val<T>)> {
+        self.bindings.iter()
+    }
+
+    fn resolve<'r>(&'r self, val: &'r Val<T>) -> &'r Val<T> {
+        let mut current = val;
+        while let Val::Var(var) = current {
+            match self.bindings.get(var) {
+                Some(next) => current = next,
+                None => break,
+            }
+        }
+        current
+    }
+
+    fn find(&mut self, val: &Val<T>) -> Val<T> {
+        let var = match val {
+            Val::Var(var) => var,
+            resolved => return resolved.clone(),
+        };
+        let next = match self.bindings.get(var) {
+            Some(Val::Var(next)) => *next,
+            Some(resolved) => return resolved.clone(),
+            None => return val.clone(),
+        };
+        if !self.bindings.contains_key(&next) {
+            return Val::Var(next);
+        }
+        // The path is at least two links long, so point every variable along
+        // the way directly at the root.
+        let root = self.resolve(val).clone();
+        let mut current = *var;
+        while let Some(Val::Var(next)) = self.bindings.get(&current) {
+            let next = *next;
+            self.bindings.insert(current, root.clone());
+            current = next;
+        }
+        root
+    }
+
+    fn bind(&mut self, key: LVar<T>, value: Val<T>) -> LVar<T> {
+        match value {
+            Val::Var(other) => {
+                let key_rank = self.rank(key);
+                let other_rank = self.rank(other);
+                let (child, parent) = if key_rank > other_rank {
+                    (other, key)
+                } else {
+                    (key, other)
+                };
+                if key_rank == other_rank {
+                    self.ranks
+                        .get_or_insert_with(HashMap::new)
+                        .insert(parent, other_rank + 1);
+                }
+                self.bindings.insert(child, Val::Var(parent));
+                child
+            }
+            resolved => {
+                self.bindings.insert(key, resolved);
+                key
+            }
+        }
+    }
+
+    fn rank(&self, var: LVar<T>) -> usize {
+        match &self.ranks {
+            Some(ranks) => ranks.get(&var).copied().unwrap_or(0),
+            None => 0,
+        }
     }
 }
+
 impl<'a, T: Debug> Clone for DomainValues<T> {
     fn clone(&self) -> Self {
-        DomainValues(self.0.clone())
+        DomainValues {
+            bindings: self.bindings.clone(),
+            ranks: self.ranks.clone(),
+        }
     }
 }
 
@@ -91,22 +189,21 @@ pub trait DomainType<'a, T: Debug>: Domain<'a> {
     where
         T: Debug,
     {
-        match val {
-            Val::Var(var) => {
-                let resolved = self.values_as_ref().0.get(var);
-                match resolved {
-                    Some(Val::Var(found)) if found == var => val,
-                    Some(found) => self.resolve(found),
-                    _ => val,
-                }
-            }
-            value => value,
-        }
+        self.values_as_ref().resolve(val)
+    }
+
+    /// Resolve a value, shortening any chain of variables that had to be
+    /// followed along the way.
+    #[doc(hidden)]
+    fn find(&mut self, val: &Val<T>) -> Val<T> {
+        self.values_as_mut().find(val)
     }
 
+    /// Bind an unbound variable to a value or another unbound variable,
+    /// returning the variable that is no longer unbound.
     #[doc(hidden)]
-    fn update(&mut self, key: LVar<T>, value: Val<T>) {
-        self.values_as_mut().0.insert(key, value);
+    fn update(&mut self, key: LVar<T>, value: Val<T>) -> LVar<T> {
+        self.values_as_mut().bind(key, value)
     }
 
     #[doc(hidden)]
@@ -161,6 +258,21 @@ pub trait DomainType<'a, T: Debug>: Domain<'a> {
 /// # fn main() {}
 /// ```
 ///
+/// Attributes such as doc comments or derives are added to the generated
+/// domain struct. With the `serde` feature enabled, deriving `Serialize` and
+/// `Deserialize` allows [resolved states](crate::state::ResolvedState) in the
+/// domain to be saved and restored. Every type in the domain must also
+/// implement both traits.
+/// ```
+/// # #[cfg(feature = "serde")]
+/// canrun::domain! {
+///     /// Values for my puzzle.
+///     #[derive(serde::Serialize, serde::Deserialize)]
+///     pub MySavedDomain { i32, String }
+/// }
+/// # fn main() {}
+/// ```
+///
 /// Any types you add to a domain must implement the
 /// [`UnifyIn`](crate::unify::UnifyIn) trait. Canrun includes default
 /// implementations for almost all primitive types and collection types are
@@ -180,3 +292,76 @@ pub trait DomainType<'a, T: Debug>: Domain<'a> {
 /// # }
 /// ```
 pub use canrun_codegen::domain;
+
+#[cfg(test)]
+mod tests {
+    use super::DomainValues;
+    use crate::example::I32;
+    use crate::value::{val, var, LVar, Val};
+    use crate::{lt, unify, util, Goal};
+
+    fn chain(length: usize) -> (DomainValues<i32>, Vec<LVar<i32>>) {
+        let mut values = DomainValues::new();
+        let vars: Vec<LVar<i32>> = (0..length).map(|_| var()).collect();
+        for pair in vars.windows(2) {
+            values.bindings.insert(pair[0], val!(pair[1]));
+        }
+        values.bind(vars[length - 1], val!(1));
+        (values, vars)
+    }
+
+    #[test]
+    fn resolves_a_chain() {
+        let (values, vars) = chain(10);
+        assert_eq!(values.resolve(&val!(vars[0])), &val!(1));
+    }
+
+    #[test]
+    fn find_compresses_the_path() {
+        let (mut values, vars) = chain(10);
+        assert_eq!(values.find(&val!(vars[0])), val!(1));
+        for var in &vars {
+            assert_eq!(values.bindings.get(var), Some(&val!(1)));
+        }
+    }
+
+    #[test]
+    fn compression_does_not_leak_into_clones() {
+        let (values, vars) = chain(3);
+        let mut compressed = values.clone();
+        compressed.find(&val!(vars[0]));
+        assert_eq!(values.bindings.get(&vars[0]), Some(&val!(vars[1])));
+    }
+
+    #[test]
+    fn union_by_rank() {
+        let mut values: DomainValues<i32> = DomainValues::new();
+        let (a, b, c) = (var(), var(), var());
+        assert_eq!(values.bind(a, val!(b)), a);
+        // `b` now has a higher rank, so `c` is attached under it even though
+        // `b` is the one being bound.
+        assert_eq!(values.bind(b, val!(c)), c);
+        assert_eq!(values.resolve(&val!(a)), &Val::Var(b));
+        assert_eq!(values.resolve(&val!(c)), &Val::Var(b));
+    }
+
+    #[test]
+    fn constraints_follow_the_joined_variable() {
+        let (x, y, z) = (var(), var(), var());
+        let goals: Vec<Goal<I32>> = vec![unify(x, z), lt(y, 5), unify(z, y), unify(x, 10)];
+        util::assert_permutations_resolve_to(goals, x, vec![]);
+
+        let goals: Vec<Goal<I32>> = vec![unify(x, z), lt(y, 5), unify(z, y), unify(x, 1)];
+        util::assert_permutations_resolve_to(goals, x, vec![1]);
+    }
+
+    #[test]
+    fn long_chains_resolve() {
+        let vars: Vec<LVar<i32>> = (0..100).map(|_| var()).collect();
+        let mut goals: Vec<Goal<I32>> = vars.windows(2).map(|p| unify(p[0], p[1])).collect();
+        goals.push(unify(vars[50], 1));
+        let goal = Goal::all(goals);
+        let results: Vec<_> = goal.query((vars[0], vars[99])).collect();
+        assert_eq!(results, vec![(1, 1)]);
+    }
+}
@@ -12,15 +12,18 @@ use crate::lvec::LVec;
 use crate::value::Val;
 
 canrun_codegen::canrun_internal_domain! {
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub I32 { i32 }
 }
 canrun_codegen::canrun_internal_domain! {
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub TupleI32 {
         i32,
         (Val<i32>, Val<i32>),
     }
 }
 canrun_codegen::canrun_internal_domain! {
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub Collections {
         i32,
         LMap<i32, i32>,
@@ -9,19 +9,28 @@
 //! While [`State`] exposes a lower level API, in practice there really
 //! shouldn't be anything that can't be expressed using goals.
 use crate::domains::Domain;
+use crate::shared::Rc;
+use crate::state::budget::{BoundedQuery, Budget};
+use crate::state::observer::Observer;
+use crate::state::search::SearchStrategy;
 use crate::state::{Constraint, Fork, State};
-use crate::state::{IterResolved, ResolvedStateIter};
+use crate::state::{IterResolved, Residual, ResidualStateIter, ResolvedStateIter};
 use crate::Query;
-use crate::ReifyIn;
-use std::rc::Rc;
+use crate::{ReifyFresh, ReifyIn};
+use std::time::Instant;
 
 mod all;
 mod any;
 mod both;
 pub mod cmp;
 mod custom;
+mod dot;
 mod either;
+mod explain;
+mod ifte;
 mod lazy;
+mod not;
+mod once;
 pub mod ops;
 pub mod project;
 mod unify;
@@ -36,11 +45,19 @@ pub use any::any;
 #[doc(inline)]
 pub use both::both;
 #[doc(inline)]
-pub use custom::custom;
+pub use custom::{custom, custom_labeled};
 #[doc(inline)]
 pub use either::either;
 #[doc(inline)]
-pub use lazy::lazy;
+pub use explain::{explain_failure, Conflict};
+#[doc(inline)]
+pub use ifte::{conda, condu, ifte};
+#[doc(inline)]
+pub use lazy::{lazy, lazy_labeled};
+#[doc(inline)]
+pub use not::not;
+#[doc(inline)]
+pub use once::once;
 #[doc(inline)]
 pub use project::*;
 #[doc(inline)]
@@ -130,6 +147,80 @@ impl<'a, D: Domain<'a> + 'a> Goal<'a, D> {
         Goal(GoalEnum::Constraint(Rc::new(constraint)))
     }
 
+    /// Create a goal that runs this goal with the [occurs
+    /// check](crate::state::State::with_occurs_check()) enabled.
+    ///
+    /// The check stays enabled for any goals that run after this one in the
+    /// same state.
+    ///
+    /// # Example
+    /// ```
+    /// use canrun::{Goal, unify, var};
+    /// use canrun::example::I32;
+    ///
+    /// let x = var();
+    /// let goal: Goal<I32> = unify(x, 1).with_occurs_check();
+    /// let result: Vec<_> = goal.query(x).collect();
+    /// assert_eq!(result, vec![1])
+    /// ```
+    pub fn with_occurs_check(self) -> Self {
+        Goal::all(vec![
+            custom::custom(|state| Some(state.with_occurs_check())),
+            self,
+        ])
+    }
+
+    /// Create a goal that runs this goal with an
+    /// [`Observer`](crate::observer::Observer) attached to the state.
+    ///
+    /// The observer sees every event from this goal and any goals that run
+    /// after it in the same state, alongside any observers that were already
+    /// attached. See the [`observer`](crate::observer) module for more
+    /// information.
+    ///
+    /// # Example
+    /// ```
+    /// use canrun::{Goal, unify, var};
+    /// use canrun::observer::LogObserver;
+    /// use canrun::example::I32;
+    /// use canrun::shared::Rc;
+    ///
+    /// let x = var();
+    /// let goal: Goal<I32> = unify(x, 1).with_observer(Rc::new(LogObserver::new()));
+    /// let result: Vec<_> = goal.query(x).collect();
+    /// assert_eq!(result, vec![1])
+    /// ```
+    pub fn with_observer(self, observer: Rc<dyn Observer + 'a>) -> Self {
+        Goal::all(vec![
+            custom::custom(move |state| Some(state.with_observer(observer.clone()))),
+            self,
+        ])
+    }
+
+    /// Create a goal that counts the work done by this goal in a
+    /// [`QueryStats`](crate::stats::QueryStats).
+    ///
+    /// This attaches the stats as the [observer](Goal::with_observer()), so
+    /// it also counts the work of any goals that run after it in the same
+    /// state. See the [`stats`](crate::stats) module for more information.
+    ///
+    /// # Example
+    /// ```
+    /// use canrun::{Goal, unify, var};
+    /// use canrun::stats::QueryStats;
+    /// use canrun::example::I32;
+    ///
+    /// let x = var();
+    /// let stats = QueryStats::new();
+    /// let goal: Goal<I32> = unify(x, 1).with_stats(&stats);
+    /// let result: Vec<_> = goal.query(x).collect();
+    /// assert_eq!(result, vec![1]);
+    /// assert_eq!(stats.unifications(), 1);
+    /// ```
+    pub fn with_stats(self, stats: &crate::stats::QueryStats) -> Self {
+        self.with_observer(Rc::new(stats.clone()))
+    }
+
     /// Create a Goal that only succeeds if all sub-goals succeed.
     ///
     /// This constructor takes anything that implements
@@ -219,10 +310,301 @@ impl<'a, D: Domain<'a> + 'a> Goal<'a, D> {
     {
         Query::query(self, query)
     }
+
+    /// Use the [query](crate::Query) interface to get an iterator of result
+    /// values, fairly interleaving the branches of every fork.
+    ///
+    /// This allows a goal with an infinite branch to still reach the
+    /// alternatives after it. See
+    /// [`.query_interleaved()`](crate::Query::query_interleaved()) for
+    /// details.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, any, unify, var};
+    /// use canrun::example::I32;
+    ///
+    /// let x = var();
+    /// let goal: Goal<I32> = any![unify(x, 1), unify(x, 2)];
+    /// let result: Vec<_> = goal.query_interleaved(x).collect();
+    /// assert_eq!(result, vec![1, 2])
+    /// ```
+    pub fn query_interleaved<Q>(self, query: Q) -> Box<dyn Iterator<Item = Q::Reified> + 'a>
+    where
+        Q: ReifyIn<'a, D> + 'a,
+    {
+        Query::query_interleaved(self, query)
+    }
+
+    /// Use the [query](crate::Query) interface to get an iterator of result
+    /// values, exploring forks with a specific [search
+    /// strategy](crate::search).
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, any, unify, var};
+    /// use canrun::search::BreadthFirst;
+    /// use canrun::example::I32;
+    ///
+    /// let x = var();
+    /// let goal: Goal<I32> = any![any![unify(x, 1), unify(x, 2)], unify(x, 3)];
+    /// let result: Vec<_> = goal.query_with(BreadthFirst, x).collect();
+    /// assert_eq!(result, vec![3, 1, 2])
+    /// ```
+    pub fn query_with<S, Q>(
+        self,
+        strategy: S,
+        query: Q,
+    ) -> Box<dyn Iterator<Item = Q::Reified> + 'a>
+    where
+        S: SearchStrategy<'a, D>,
+        Q: ReifyIn<'a, D> + 'a,
+    {
+        Query::query_with(self, strategy, query)
+    }
+
+    /// Use the [query](crate::Query) interface to get an iterator of result
+    /// values, giving up once the [`Budget`](crate::budget::Budget) runs out.
+    ///
+    /// See [`.query_bounded()`](crate::Query::query_bounded()) for details.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, any, unify, var};
+    /// use canrun::budget::Budget;
+    /// use canrun::example::I32;
+    ///
+    /// let x = var();
+    /// let budget = Budget::new().max_steps(100);
+    /// let goal: Goal<I32> = any![unify(x, 1), unify(x, 2)];
+    /// let mut query = goal.query_bounded(&budget, x);
+    /// assert_eq!(query.by_ref().collect::<Vec<_>>(), vec![1, 2]);
+    /// assert_eq!(query.interrupted(), None);
+    /// ```
+    pub fn query_bounded<Q>(self, budget: &Budget, query: Q) -> BoundedQuery<'a, Q::Reified>
+    where
+        Q: ReifyIn<'a, D> + 'a,
+    {
+        Query::query_bounded(self, budget, query)
+    }
+
+    /// Use the [query](crate::Query) interface to get an iterator of result
+    /// values, giving up once the deadline passes.
+    ///
+    /// See [`.query_until()`](crate::Query::query_until()) for details.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, Interrupt, any, unify, var};
+    /// use canrun::example::I32;
+    /// use std::time::Instant;
+    ///
+    /// let x = var();
+    /// let goal: Goal<I32> = any![unify(x, 1), unify(x, 2)];
+    /// let mut query = goal.query_until(Instant::now(), x);
+    /// assert_eq!(query.next(), None);
+    /// assert_eq!(query.interrupted(), Some(Interrupt::Deadline));
+    /// ```
+    pub fn query_until<Q>(self, deadline: Instant, query: Q) -> BoundedQuery<'a, Q::Reified>
+    where
+        Q: ReifyIn<'a, D> + 'a,
+    {
+        Query::query_until(self, deadline, query)
+    }
+
+    /// Use the [query](crate::Query) interface to get an iterator of result
+    /// values, including those with constraints that are still pending.
+    ///
+    /// See [`.query_residual()`](crate::Query::query_residual()) for details.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, lt, unify, all, var};
+    /// use canrun::example::I32;
+    ///
+    /// let (x, y) = (var(), var());
+    /// let goal: Goal<I32> = all![unify(x, 1), lt(x, y)];
+    /// let residual = goal.query_residual(x).next().unwrap();
+    /// assert_eq!(residual.value, Some(1));
+    /// assert_eq!(residual.constraints.len(), 1);
+    /// ```
+    pub fn query_residual<Q>(self, query: Q) -> Box<dyn Iterator<Item = Residual<Q::Reified>> + 'a>
+    where
+        Q: ReifyIn<'a, D> + 'a,
+    {
+        Query::query_residual(self, query)
+    }
+
+    /// Use the [query](crate::Query) interface to get an iterator of result
+    /// values, with unbound variables replaced by placeholders.
+    ///
+    /// See [`.query_fresh()`](crate::Query::query_fresh()) for details.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, Reified, unify, var};
+    /// use canrun::example::I32;
+    ///
+    /// let (x, y) = (var(), var());
+    /// let goal: Goal<I32> = unify(x, y);
+    /// let result: Vec<_> = goal.query_fresh((x, y)).collect();
+    /// assert_eq!(result, vec![(Reified::Fresh(0), Reified::Fresh(0))]);
+    /// ```
+    pub fn query_fresh<Q>(self, query: Q) -> Box<dyn Iterator<Item = Q::Reified> + 'a>
+    where
+        Q: ReifyFresh<'a, D> + 'a,
+    {
+        Query::query_fresh(self, query)
+    }
+
+    /// Check whether this goal has at least one solution.
+    ///
+    /// The search stops at the first [resolved
+    /// state](crate::state::ResolvedState), and no values are
+    /// [reified](crate::value::ReifyIn).
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, all, unify, var};
+    /// use canrun::example::I32;
+    ///
+    /// let x = var();
+    /// let goal: Goal<I32> = unify(x, 1);
+    /// assert!(goal.has_solution());
+    ///
+    /// let goal: Goal<I32> = all![unify(x, 1), unify(x, 2)];
+    /// assert!(!goal.has_solution());
+    /// ```
+    pub fn has_solution(&self) -> bool {
+        self.clone().iter_resolved().next().is_some()
+    }
+
+    /// Count the solutions to this goal without
+    /// [reifying](crate::value::ReifyIn) any values.
+    ///
+    /// The whole search space is explored, so goals with infinitely many
+    /// solutions will never finish.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, any, unify, var};
+    /// use canrun::example::I32;
+    ///
+    /// let x = var();
+    /// let goal: Goal<I32> = any![unify(x, 1), unify(x, 2), unify(x, 3)];
+    /// assert_eq!(goal.count_solutions(), 3);
+    /// ```
+    pub fn count_solutions(&self) -> usize {
+        self.clone().iter_resolved().count()
+    }
+
+    /// Check whether this goal has exactly one solution.
+    ///
+    /// No values are [reified](crate::value::ReifyIn). Instead, each
+    /// [resolved state](crate::state::ResolvedState) is compared with the
+    /// first by its bindings, so branches that arrive at the same bindings
+    /// count as one solution. The search stops as soon as a second, different
+    /// state is found, but has to run to the end to show that there isn't one.
+    ///
+    /// States are only the same when they bind every variable the same way,
+    /// including any that were created along the way to them.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, all, any, unify, var};
+    /// use canrun::example::I32;
+    ///
+    /// let x = var();
+    /// let goal: Goal<I32> = all![any![unify(x, 1), unify(x, 2)], unify(x, 2)];
+    /// assert!(goal.is_unique());
+    ///
+    /// // Both branches reach `x == 2`, which is still one solution.
+    /// let goal: Goal<I32> = all![any![unify(x, 2), unify(x, 2)], unify(x, 2)];
+    /// assert!(goal.is_unique());
+    ///
+    /// let goal: Goal<I32> = any![unify(x, 1), unify(x, 2)];
+    /// assert!(!goal.is_unique());
+    /// ```
+    pub fn is_unique(&self) -> bool {
+        let mut states = self.clone().iter_resolved();
+        match states.next() {
+            Some(first) => states.all(|other| other.same_bindings(&first)),
+            None => false,
+        }
+    }
 }
 
 impl<'a, D: Domain<'a> + 'a> IterResolved<'a, D> for Goal<'a, D> {
-    fn iter_resolved(self) -> ResolvedStateIter<'a, D> {
-        self.apply(State::new()).iter_resolved()
+    fn iter_resolved_with<S>(self, strategy: S) -> ResolvedStateIter<'a, D>
+    where
+        S: SearchStrategy<'a, D>,
+    {
+        self.apply(strategy.prepare(State::new()))
+            .iter_resolved_with(strategy)
+    }
+
+    fn iter_residual_with<S>(self, strategy: S) -> ResidualStateIter<'a, D>
+    where
+        S: SearchStrategy<'a, D>,
+    {
+        self.apply(strategy.prepare(State::new()))
+            .iter_residual_with(strategy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::example::{Collections, I32};
+    use crate::lvec::{self, lvec};
+    use crate::{both, either, lazy, unify, var, Goal, LVar};
+
+    fn ones(x: LVar<i32>) -> Goal<'static, I32> {
+        either(unify(x, 1), lazy(move || ones(x)))
+    }
+
+    #[test]
+    fn solution_checks_stop_early() {
+        let x = var();
+        assert!(ones(x).has_solution());
+        assert!(!either(unify(x, 2), ones(x)).is_unique());
+    }
+
+    #[test]
+    fn failing_goal_has_no_solutions() {
+        let goal: Goal<I32> = unify(1, 2);
+        assert!(!goal.has_solution());
+        assert!(!goal.is_unique());
+        assert_eq!(goal.count_solutions(), 0);
+    }
+
+    #[test]
+    fn uniqueness_ignores_duplicate_paths() {
+        let (x, y) = (var(), var());
+        let goal: Goal<I32> = either(unify(x, 1), unify(x, 1));
+        assert_eq!(goal.count_solutions(), 2);
+        assert!(goal.is_unique());
+
+        let goal: Goal<I32> = either(
+            both(unify(x, y), unify(y, 1)),
+            both(unify(y, x), unify(x, 1)),
+        );
+        assert!(goal.is_unique());
+
+        let goal: Goal<I32> = either(unify(x, 1), both(unify(x, 1), unify(y, 2)));
+        assert!(!goal.is_unique());
+    }
+
+    #[test]
+    fn duplicate_members_are_unique() {
+        let x = var();
+        let goal: Goal<Collections> = Goal::all(vec![
+            lvec::member(1, x),
+            lvec::member(1, x),
+            unify(x, lvec![1, 2]),
+        ]);
+        assert!(goal.is_unique());
+        let goal: Goal<Collections> = Goal::all(vec![lvec::member(1, x), unify(x, lvec![1, 1])]);
+        assert_eq!(goal.count_solutions(), 2);
+        assert!(goal.is_unique());
     }
 }
@@ -1,4 +1,4 @@
-use super::GoalEnum;
+use super::{Goal, GoalEnum};
 use crate::domains::Domain;
 use crate::state::{Fork, State};
 use std::iter::repeat;
@@ -20,6 +20,14 @@ where
         let states = repeat(state);
         Box::new(goals.zip(states).flat_map(|(g, s)| g.apply(s).into_iter()))
     }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.goals.len())
+    }
+
+    fn branches(&self) -> Vec<Goal<'a, D>> {
+        self.goals.iter().cloned().map(Goal).collect()
+    }
 }
 
 /// Create a [goal](crate::goals::Goal) that yields a state for every successful
@@ -1,5 +1,6 @@
 use crate::assert_2;
 use crate::goals::Goal;
+use crate::shared::Shareable;
 use crate::value::IntoVal;
 use crate::DomainType;
 use std::fmt::Debug;
@@ -23,8 +24,8 @@ use std::fmt::Debug;
 /// ```
 pub fn lt<'a, A, AV, B, BV, D>(a: AV, b: BV) -> Goal<'a, D>
 where
-    A: PartialOrd<B> + Debug + 'a,
-    B: Debug + 'a,
+    A: PartialOrd<B> + Debug + Shareable + 'a,
+    B: Debug + Shareable + 'a,
     AV: IntoVal<A>,
     BV: IntoVal<B>,
     D: DomainType<'a, A> + DomainType<'a, B>,
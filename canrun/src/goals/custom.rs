@@ -1,11 +1,13 @@
 use super::{Goal, GoalEnum};
 use crate::domains::Domain;
+use crate::shared::{Rc, Shareable};
 use crate::state::State;
 use std::fmt;
-use std::rc::Rc;
+
+type CustomFn<'a, D> = dyn_fn!(Fn(State<'a, D>) -> Option<State<'a, D>> + 'a);
 
 #[derive(Clone)]
-pub struct Custom<'a, D: Domain<'a>>(Rc<dyn Fn(State<'a, D>) -> Option<State<'a, D>> + 'a>);
+pub struct Custom<'a, D: Domain<'a>>(Rc<CustomFn<'a, D>>, Option<&'static str>);
 
 impl<'a, D: Domain<'a>> Custom<'a, D> {
     pub(crate) fn run(self, state: State<'a, D>) -> Option<State<'a, D>>
@@ -15,6 +17,10 @@ impl<'a, D: Domain<'a>> Custom<'a, D> {
         let func = self.0;
         func(state)
     }
+
+    pub(super) fn label(&self) -> Option<&'static str> {
+        self.1
+    }
 }
 
 /// Create a [goal](crate::goals::Goal) that gives access to the underlying
@@ -47,20 +53,47 @@ impl<'a, D: Domain<'a>> Custom<'a, D> {
 pub fn custom<'a, D, F>(func: F) -> Goal<'a, D>
 where
     D: Domain<'a>,
-    F: Fn(State<'a, D>) -> Option<State<'a, D>> + 'a,
+    F: Fn(State<'a, D>) -> Option<State<'a, D>> + Shareable + 'a,
 {
-    Goal(GoalEnum::Custom(Custom(Rc::new(func))))
+    Goal(GoalEnum::Custom(Custom(Rc::new(func), None)))
+}
+
+/// Create a [`custom`] goal with a label.
+///
+/// The label is shown in the `Debug` output and in
+/// [`Goal::to_dot()`](crate::goals::Goal::to_dot()) in place of the callback,
+/// which can't be inspected.
+///
+/// # Examples
+///
+/// ```
+/// use canrun::{Goal, custom_labeled, val, var};
+/// use canrun::example::I32;
+///
+/// let x = var();
+/// let goal: Goal<I32> = custom_labeled("x is 1", move |state| state.unify(&val!(x), &val!(1)));
+/// assert_eq!(format!("{:?}", goal), "Goal(Custom(Custom x is 1))");
+/// ```
+pub fn custom_labeled<'a, D, F>(label: &'static str, func: F) -> Goal<'a, D>
+where
+    D: Domain<'a>,
+    F: Fn(State<'a, D>) -> Option<State<'a, D>> + Shareable + 'a,
+{
+    Goal(GoalEnum::Custom(Custom(Rc::new(func), Some(label))))
 }
 
 impl<'a, D: Domain<'a>> fmt::Debug for Custom<'a, D> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Custom ??")
+        match self.1 {
+            Some(label) => write!(f, "Custom {}", label),
+            None => write!(f, "Custom ??"),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::custom;
+    use super::{custom, custom_labeled};
     use crate::example::I32;
     use crate::goals::Goal;
     use crate::util;
@@ -73,4 +106,13 @@ mod tests {
         let results = util::goal_resolves_to(goal, x);
         assert_eq!(results, vec![1]);
     }
+
+    #[test]
+    fn labeled_succeeds() {
+        let x = var::<i32>();
+        let goal: Goal<I32> = custom_labeled("x", move |s| s.unify(&x.into_val(), &1.into_val()));
+        assert_eq!(format!("{:?}", goal), "Goal(Custom(Custom x))");
+        let results = util::goal_resolves_to(goal, x);
+        assert_eq!(results, vec![1]);
+    }
 }
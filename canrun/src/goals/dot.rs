@@ -0,0 +1,269 @@
+use super::{Goal, GoalEnum};
+use crate::domains::Domain;
+use crate::state::search::Expansion;
+use crate::state::State;
+use std::fmt::Write;
+
+/// Builds up a graph in the [DOT](https://graphviz.org/doc/info/lang.html)
+/// language, one node at a time.
+struct Dot {
+    out: String,
+    nodes: usize,
+}
+
+impl Dot {
+    fn new() -> Self {
+        Dot {
+            out: String::from("digraph {\n    node [shape=box];\n"),
+            nodes: 0,
+        }
+    }
+
+    fn node(&mut self, label: &str, attributes: &str) -> usize {
+        let id = self.nodes;
+        self.nodes += 1;
+        let label = label
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n");
+        writeln!(self.out, "    n{} [label=\"{}\"{}];", id, label, attributes).unwrap();
+        id
+    }
+
+    fn edge(&mut self, from: usize, to: usize) {
+        writeln!(self.out, "    n{} -> n{};", from, to).unwrap();
+    }
+
+    fn finish(mut self) -> String {
+        self.out.push_str("}\n");
+        self.out
+    }
+}
+
+const SUCCESS: &str = ", color=green";
+const FAILURE: &str = ", color=red";
+
+fn goal_node<'a, D: Domain<'a> + 'a>(dot: &mut Dot, goal: &GoalEnum<'a, D>) -> usize {
+    match goal {
+        GoalEnum::Succeed => dot.node("succeed", ""),
+        GoalEnum::Fail => dot.node("fail", ""),
+        GoalEnum::UnifyIn(a, b) => dot.node(&format!("unify {:?} = {:?}", a, b), ""),
+        GoalEnum::Fork(fork) => {
+            let branches = fork.branches();
+            if branches.is_empty() {
+                return dot.node(&format!("{:?}", fork), "");
+            }
+            let id = dot.node("fork", "");
+            for branch in &branches {
+                let child = goal_node(dot, &branch.0);
+                dot.edge(id, child);
+            }
+            id
+        }
+        GoalEnum::Constraint(constraint) => dot.node(&format!("{:?}", constraint), ""),
+        GoalEnum::Both(a, b) => {
+            let id = dot.node("both", "");
+            for child in &[a, b] {
+                let child = goal_node(dot, child);
+                dot.edge(id, child);
+            }
+            id
+        }
+        GoalEnum::All(goals) => {
+            let id = dot.node("all", "");
+            for child in goals {
+                let child = goal_node(dot, child);
+                dot.edge(id, child);
+            }
+            id
+        }
+        GoalEnum::Lazy(lazy) => dot.node(lazy.label().unwrap_or("lazy"), ""),
+        GoalEnum::Custom(custom) => dot.node(custom.label().unwrap_or("custom"), ""),
+    }
+}
+
+fn search_node<'a, D: Domain<'a> + 'a>(dot: &mut Dot, state: State<'a, D>) -> usize {
+    let size_hint = state.peek_fork().and_then(|fork| fork.size_hint());
+    match state.expand() {
+        Expansion::Settled(state) => {
+            if state.pending_constraints().is_empty() {
+                dot.node("solution", SUCCESS)
+            } else {
+                dot.node("unresolved constraints", FAILURE)
+            }
+        }
+        Expansion::Forked(states) => {
+            let id = dot.node("fork", "");
+            let mut found = 0;
+            for state in states {
+                let child = search_node(dot, state);
+                dot.edge(id, child);
+                found += 1;
+            }
+            // Forks only yield the branches that succeed, so any shortfall
+            // from the hint is drawn as failed branches. Without a hint
+            // there's no telling how many failed, only that none succeeded.
+            match size_hint {
+                Some(size) => {
+                    for _ in found..size {
+                        let child = dot.node("fail", FAILURE);
+                        dot.edge(id, child);
+                    }
+                }
+                None if found == 0 => {
+                    let child = dot.node("exhausted", FAILURE);
+                    dot.edge(id, child);
+                }
+                None => {}
+            }
+            id
+        }
+    }
+}
+
+impl<'a, D: Domain<'a> + 'a> Goal<'a, D> {
+    /// Render the structure of this goal as a graph in the
+    /// [DOT](https://graphviz.org/doc/info/lang.html) language.
+    ///
+    /// Combinators such as [`all!`](crate::goals::all!) and
+    /// [`any!`](crate::goals::any!) become nodes with an edge to each
+    /// sub-goal. Other goals are drawn using their `Debug` output, except
+    /// [`lazy`](crate::goals::lazy()) and [`custom`](crate::goals::custom())
+    /// goals, which can't be inspected. Use
+    /// [`lazy_labeled`](crate::goals::lazy_labeled()) or
+    /// [`custom_labeled`](crate::goals::custom_labeled()) to tell them apart.
+    ///
+    /// The output can be turned into an image with
+    /// [Graphviz](https://graphviz.org), e.g. `dot -Tsvg goal.dot`.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, all, any, lazy_labeled, unify, var};
+    /// use canrun::example::I32;
+    ///
+    /// let x = var();
+    /// let goal: Goal<I32> = all![
+    ///     any![unify(x, 1), unify(x, 2)],
+    ///     lazy_labeled("x is 2", move || unify(x, 2)),
+    /// ];
+    /// let dot = goal.to_dot();
+    /// assert!(dot.starts_with("digraph {"));
+    /// assert!(dot.contains("[label=\"x is 2\"]"));
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut dot = Dot::new();
+        goal_node(&mut dot, &self.0);
+        dot.finish()
+    }
+
+    /// Run this goal and render the tree of states it explored as a graph in
+    /// the [DOT](https://graphviz.org/doc/info/lang.html) language.
+    ///
+    /// Each expanded [fork](crate::state::Fork) is a node with an edge to each
+    /// state it produced. Results are drawn in green. Branches that failed are
+    /// drawn in red, along with states that were left with [constraints
+    /// waiting](crate::state::State::constrain()) on unbound variables. Failed
+    /// branches are counted using the fork's
+    /// [`size_hint`](crate::state::Fork::size_hint()). Forks without one can't
+    /// be counted, and only get a single "exhausted" node when none of their
+    /// branches succeed.
+    ///
+    /// The whole search space is explored depth first, so goals with an
+    /// infinite search space will never finish.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, all, any, unify, var};
+    /// use canrun::example::I32;
+    ///
+    /// let x = var();
+    /// let goal: Goal<I32> = all![any![unify(x, 1), unify(x, 2)], unify(x, 2)];
+    /// let dot = goal.search_to_dot();
+    /// assert_eq!(dot.matches("solution").count(), 1);
+    /// assert_eq!(dot.matches("\"fail\"").count(), 1);
+    /// ```
+    pub fn search_to_dot(&self) -> String {
+        let mut dot = Dot::new();
+        match self.clone().apply(State::new()) {
+            Some(state) => search_node(&mut dot, state),
+            None => dot.node("fail", FAILURE),
+        };
+        dot.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate as canrun;
+    use crate::example::I32;
+    use crate::{all, any, custom_labeled, either, lazy, lt, once, unify, var, Goal};
+
+    fn count(dot: &str, needle: &str) -> usize {
+        dot.matches(needle).count()
+    }
+
+    #[test]
+    fn draws_combinators_with_edges() {
+        let x = var();
+        let goal: Goal<I32> = all![
+            either(unify(x, 1), unify(x, 2)),
+            any![unify(x, 2), unify(x, 3), Goal::fail()],
+        ];
+        let dot = goal.to_dot();
+        assert_eq!(count(&dot, "[label=\"all\"]"), 1);
+        assert_eq!(count(&dot, "[label=\"fork\"]"), 2);
+        assert_eq!(count(&dot, "[label=\"unify"), 4);
+        assert_eq!(count(&dot, " -> "), 7);
+    }
+
+    #[test]
+    fn labels_lazy_and_custom_goals() {
+        let goal: Goal<I32> = all![lazy(Goal::succeed), custom_labeled("keep", Some)];
+        let dot = goal.to_dot();
+        assert!(dot.contains("[label=\"lazy\"]"));
+        assert!(dot.contains("[label=\"keep\"]"));
+    }
+
+    #[test]
+    fn escapes_labels() {
+        let goal: Goal<I32> = custom_labeled("say \"hi\"", Some);
+        assert!(goal.to_dot().contains("[label=\"say \\\"hi\\\"\"]"));
+    }
+
+    #[test]
+    fn marks_explored_successes_and_failures() {
+        let x = var();
+        let goal: Goal<I32> = all![
+            any![unify(x, 1), unify(x, 2), unify(x, 3)],
+            either(unify(x, 2), unify(x, 3)),
+        ];
+        let dot = goal.search_to_dot();
+        assert_eq!(count(&dot, "solution"), 2);
+        assert_eq!(count(&dot, "\"fail\""), 4);
+    }
+
+    #[test]
+    fn marks_hintless_forks_as_exhausted() {
+        let x = var();
+        let goal: Goal<I32> = all![once(any![unify(x, 1), unify(x, 2)]), unify(x, 3)];
+        let dot = goal.search_to_dot();
+        assert_eq!(count(&dot, "\"exhausted\""), 1);
+        assert_eq!(count(&dot, "\"fail\""), 0);
+    }
+
+    #[test]
+    fn marks_unresolved_constraints_as_failures() {
+        let (x, y) = (var(), var());
+        let goal: Goal<I32> = all![lt(x, y), unify(x, 1)];
+        let dot = goal.search_to_dot();
+        assert!(dot.contains("unresolved constraints"));
+    }
+
+    #[test]
+    fn immediate_failure_is_a_single_node() {
+        let goal: Goal<I32> = unify(1, 2);
+        let dot = goal.search_to_dot();
+        assert_eq!(count(&dot, "[label="), 1);
+        assert!(dot.contains("color=red"));
+    }
+}
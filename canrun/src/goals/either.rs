@@ -1,6 +1,7 @@
 use super::{Goal, GoalEnum};
 use crate::domains::Domain;
 use crate::state::{Fork, State};
+use std::iter::from_fn;
 
 #[derive(Debug)]
 struct Either<'a, D>
@@ -16,9 +17,26 @@ where
     D: Domain<'a>,
 {
     fn fork(&self, state: State<'a, D>) -> crate::state::StateIter<'a, D> {
-        let a = self.a.clone().apply(state.clone()).into_iter();
-        let b = self.b.clone().apply(state).into_iter();
-        Box::new(a.chain(b))
+        // Each branch is only applied once the iterator reaches it. The last
+        // branch takes the original state, so it is only cloned once.
+        let mut a = Some(self.a.clone());
+        let mut b = Some(self.b.clone());
+        let mut state = Some(state);
+        Box::new(
+            from_fn(move || match a.take() {
+                Some(a) => Some(a.apply(state.clone()?)),
+                None => Some(b.take()?.apply(state.take()?)),
+            })
+            .flatten(),
+        )
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(2)
+    }
+
+    fn branches(&self) -> Vec<Goal<'a, D>> {
+        vec![Goal(self.a.clone()), Goal(self.b.clone())]
     }
 }
 
@@ -74,9 +92,10 @@ mod tests {
     use super::either;
     use crate::example::I32;
     use crate::goals::unify::unify;
-    use crate::goals::Goal;
+    use crate::goals::{both, custom, Goal};
     use crate::util;
     use crate::value::var;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     #[test]
     fn either_both_succeeds() {
@@ -105,4 +124,22 @@ mod tests {
         let results = util::goal_resolves_to(goal, x);
         assert_eq!(results, vec![] as Vec<i32>);
     }
+
+    #[test]
+    fn either_applies_branches_lazily() {
+        static APPLIED: AtomicUsize = AtomicUsize::new(0);
+        let x = var();
+        let counted = |value| {
+            both(
+                custom(|state| {
+                    APPLIED.fetch_add(1, Ordering::SeqCst);
+                    Some(state)
+                }),
+                unify(x, value),
+            )
+        };
+        let goal: Goal<I32> = either(counted(1), counted(2));
+        assert_eq!(goal.query(x).next(), Some(1));
+        assert_eq!(APPLIED.load(Ordering::SeqCst), 1);
+    }
 }
@@ -0,0 +1,173 @@
+use super::{Goal, GoalEnum};
+use crate::domains::Domain;
+use crate::state::IterResolved;
+use std::fmt;
+
+/// A minimal set of goals that cannot all succeed together.
+///
+/// Returned from [`explain_failure`]. Removing any one of these goals would
+/// allow the rest to succeed.
+#[derive(Clone)]
+pub struct Conflict<'a, D: Domain<'a>> {
+    /// The conflicting goals.
+    pub goals: Vec<Goal<'a, D>>,
+    /// The position of each conflicting goal in the original list.
+    pub indices: Vec<usize>,
+}
+
+impl<'a, D: Domain<'a>> fmt::Debug for Conflict<'a, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.indices.iter().zip(self.goals.iter()))
+            .finish()
+    }
+}
+
+impl<'a, D: Domain<'a>> fmt::Display for Conflict<'a, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, goal) in self.indices.iter().zip(self.goals.iter()) {
+            writeln!(f, "[{}] {:?}", index, goal)?;
+        }
+        Ok(())
+    }
+}
+
+/// Find a minimal subset of goals that has no solutions.
+///
+/// Given a list of goals that fail when combined with
+/// [`all!`](crate::goals::all!), this returns a [`Conflict`] where every goal
+/// is needed to cause the failure. If the goals do have a solution, `None` is
+/// returned.
+///
+/// Goals that only leave [constraints](crate::state::State::constrain())
+/// waiting on unbound variables are not treated as failures, even though
+/// [`.query()`](crate::Query::query()) would not return them. Otherwise a
+/// lone constraint would always look like the cause.
+///
+/// Goals are removed one at a time, and each removal is kept as long as the
+/// rest still fail. This means one search for every goal in the list, so goals
+/// with an infinite search space may never finish.
+///
+/// # Example:
+/// ```
+/// use canrun::{Goal, explain_failure, unify, var};
+/// use canrun::example::I32;
+///
+/// let (x, y, z) = (var(), var(), var());
+/// let goals: Vec<Goal<I32>> = vec![
+///     unify(x, 1),
+///     unify(y, 2),
+///     unify(z, 3),
+///     unify(x, y),
+/// ];
+/// let conflict = explain_failure(goals).unwrap();
+/// assert_eq!(conflict.indices, vec![0, 1, 3]);
+/// ```
+pub fn explain_failure<'a, D, I>(goals: I) -> Option<Conflict<'a, D>>
+where
+    D: Domain<'a> + 'a,
+    I: IntoIterator<Item = Goal<'a, D>>,
+{
+    let goals: Vec<(usize, Goal<'a, D>)> = goals.into_iter().enumerate().collect();
+    if succeeds(&goals) {
+        return None;
+    }
+
+    let mut needed = goals;
+    let mut index = 0;
+    while index < needed.len() {
+        let mut without = needed.clone();
+        without.remove(index);
+        if succeeds(&without) {
+            index += 1;
+        } else {
+            needed = without;
+        }
+    }
+
+    let (indices, goals) = needed.into_iter().unzip();
+    Some(Conflict { goals, indices })
+}
+
+fn succeeds<'a, D: Domain<'a> + 'a>(goals: &[(usize, Goal<'a, D>)]) -> bool {
+    let goals = goals.iter().map(|(_, goal)| goal.clone());
+    Goal::all(goals).iter_residual().next().is_some()
+}
+
+impl<'a, D: Domain<'a> + 'a> Goal<'a, D> {
+    /// Find a minimal subset of the sub-goals of an [`all`](crate::goals::all!)
+    /// that has no solutions.
+    ///
+    /// Any other kind of goal is treated as a list of one. See
+    /// [`explain_failure`] for details.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, all, unify, var};
+    /// use canrun::example::I32;
+    ///
+    /// let x = var();
+    /// let goal: Goal<I32> = all![unify(x, 1), unify(x, 1), unify(x, 2)];
+    /// let conflict = goal.explain_failure().unwrap();
+    /// assert_eq!(conflict.indices, vec![1, 2]);
+    /// ```
+    pub fn explain_failure(&self) -> Option<Conflict<'a, D>> {
+        match &self.0 {
+            GoalEnum::All(goals) => explain_failure(goals.iter().cloned().map(Goal)),
+            _ => explain_failure(vec![self.clone()]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::explain_failure;
+    use crate as canrun;
+    use crate::example::I32;
+    use crate::{all, any, lt, unify, var, Goal};
+
+    #[test]
+    fn satisfiable_goals_have_no_conflict() {
+        let x = var();
+        let goals: Vec<Goal<I32>> = vec![unify(x, 1), unify(x, 1)];
+        assert!(explain_failure(goals).is_none());
+    }
+
+    #[test]
+    fn finds_a_single_failing_goal() {
+        let x = var();
+        let goals: Vec<Goal<I32>> = vec![unify(x, 1), Goal::fail(), unify(x, 1)];
+        let conflict = explain_failure(goals).unwrap();
+        assert_eq!(conflict.indices, vec![1]);
+    }
+
+    #[test]
+    fn finds_a_conflict_among_many_goals() {
+        let vars: Vec<_> = (0..20).map(|_| var()).collect();
+        let mut goals: Vec<Goal<I32>> = vars
+            .iter()
+            .enumerate()
+            .map(|(n, v)| unify(*v, n as i32))
+            .collect();
+        goals.push(lt(vars[15], vars[3]));
+        let conflict = explain_failure(goals).unwrap();
+        assert_eq!(conflict.indices, vec![3, 15, 20]);
+    }
+
+    #[test]
+    fn considers_forks() {
+        let x = var();
+        let goals: Vec<Goal<I32>> = vec![any![unify(x, 1), unify(x, 2)], unify(x, 3), unify(x, 3)];
+        let conflict = explain_failure(goals).unwrap();
+        assert_eq!(conflict.indices, vec![0, 2]);
+    }
+
+    #[test]
+    fn explains_an_all_goal() {
+        let (x, y) = (var(), var());
+        let goal: Goal<I32> = all![unify(x, 1), unify(y, 2), unify(x, y)];
+        let conflict = goal.explain_failure().unwrap();
+        assert_eq!(conflict.indices, vec![0, 1, 2]);
+        assert_eq!(conflict.to_string().lines().count(), 3);
+    }
+}
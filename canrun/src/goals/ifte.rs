@@ -0,0 +1,284 @@
+use super::Goal;
+use crate::domains::Domain;
+use crate::state::{Fork, State, StateIter};
+
+#[derive(Debug)]
+struct Ifte<'a, D: Domain<'a>> {
+    cond: Goal<'a, D>,
+    then: Goal<'a, D>,
+    otherwise: Goal<'a, D>,
+}
+
+impl<'a, D> Fork<'a, D> for Ifte<'a, D>
+where
+    D: Domain<'a> + 'a,
+{
+    fn fork(&self, state: State<'a, D>) -> StateIter<'a, D> {
+        let mut solutions = state.clone().search_own_forks(self.cond.clone()).peekable();
+        if solutions.peek().is_some() {
+            let then = self.then.clone();
+            Box::new(solutions.filter_map(move |state| then.clone().apply(state)))
+        } else if state.interrupted() {
+            // Running out of budget doesn't mean that `cond` has no solutions.
+            Box::new(std::iter::empty())
+        } else {
+            Box::new(self.otherwise.clone().apply(state).into_iter())
+        }
+    }
+}
+
+/// Create a [goal](crate::goals::Goal) that runs `then` on every solution of
+/// `cond`, or `otherwise` if `cond` has none.
+///
+/// This is a "soft cut": all of the solutions of `cond` are kept, but once
+/// there is at least one, `otherwise` is never tried. Solutions are found
+/// by searching `cond` depth first, without expanding any other pending
+/// alternatives in the state. Like the other forking goals, this is only
+/// evaluated once the rest of the goals in the state have been applied.
+///
+/// The depth first search of `cond` is used whatever [search
+/// strategy](crate::search) the query runs with, so a `cond` with an endless
+/// branch before its first solution will never finish, even in
+/// [`.query_interleaved()`](crate::Query::query_interleaved()). A
+/// [`Budget`](crate::budget::Budget) still stops it, in which case the goal
+/// fails rather than running `otherwise`.
+///
+/// See [`conda!`](crate::goals::conda!) for a chain of conditions.
+///
+/// # Example:
+/// ```
+/// use canrun::{Goal, ifte, unify, var};
+/// use canrun::example::I32;
+///
+/// let (x, y) = (var(), var());
+/// let goal: Goal<I32> = ifte(unify(x, 1), unify(y, 10), unify(y, 20));
+/// let result: Vec<_> = goal.query((x, y)).collect();
+/// assert_eq!(result, vec![(1, 10)]);
+///
+/// let goal: Goal<I32> = Goal::all(vec![
+///     unify(x, 2),
+///     ifte(unify(x, 1), unify(y, 10), unify(y, 20)),
+/// ]);
+/// let result: Vec<_> = goal.query((x, y)).collect();
+/// assert_eq!(result, vec![(2, 20)]);
+/// ```
+pub fn ifte<'a, D>(cond: Goal<'a, D>, then: Goal<'a, D>, otherwise: Goal<'a, D>) -> Goal<'a, D>
+where
+    D: Domain<'a> + 'a,
+{
+    Goal::fork(Ifte {
+        cond,
+        then,
+        otherwise,
+    })
+}
+
+/// Create a [goal](crate::goals::Goal) that commits to the first clause
+/// with a successful condition.
+///
+/// Each clause is a bracketed list of goals, where the first one is the
+/// condition. The rest of the goals in a clause are only run on the solutions
+/// of its condition, and the later clauses are only tried if the condition
+/// has no solutions. If no condition succeeds, the goal fails.
+///
+/// Conditions are searched depth first no matter which [search
+/// strategy](crate::search) the query uses. See [`ifte`](crate::goals::ifte())
+/// for details.
+///
+/// This is a chain of [`ifte`](crate::goals::ifte()) goals. See
+/// [`condu!`](crate::goals::condu!) to also keep only the first solution of
+/// each condition.
+///
+/// # Example:
+/// ```
+/// use canrun::{Goal, any, conda, unify, var};
+/// use canrun::example::I32;
+///
+/// let (x, y) = (var(), var());
+/// let goal: Goal<I32> = conda![
+///     [unify(x, 1), unify(y, 10)],
+///     [any![unify(x, 2), unify(x, 3)], unify(y, 20)],
+///     [Goal::succeed(), unify(y, 30)],
+/// ];
+/// let result: Vec<_> = goal.query((x, y)).collect();
+/// assert_eq!(result, vec![(1, 10)]);
+/// ```
+#[macro_export]
+macro_rules! conda {
+    ([$cond:expr $(, $goal:expr)* $(,)?] $(, [$($rest:tt)*])* $(,)?) => {
+        canrun::goals::ifte(
+            $cond,
+            canrun::goals::Goal::all(vec![$($goal),*]),
+            canrun::conda!($([$($rest)*]),*),
+        )
+    };
+    () => {
+        canrun::goals::Goal::fail()
+    };
+}
+pub use conda;
+
+/// Create a [goal](crate::goals::Goal) that commits to the first solution of
+/// the first clause with a successful condition.
+///
+/// This works just like [`conda!`](crate::goals::conda!), except that only
+/// the first solution of each condition is kept, as with
+/// [`once`](crate::goals::once()).
+///
+/// # Example:
+/// ```
+/// use canrun::{Goal, any, condu, unify, var};
+/// use canrun::example::I32;
+///
+/// let (x, y) = (var(), var());
+/// let goal: Goal<I32> = condu![
+///     [unify(x, 1), unify(y, 10)],
+///     [any![unify(x, 2), unify(x, 3)], unify(y, 20)],
+/// ];
+/// let result: Vec<_> = goal.query((x, y)).collect();
+/// assert_eq!(result, vec![(1, 10)]);
+///
+/// let goal: Goal<I32> = condu![[any![unify(x, 2), unify(x, 3)], unify(y, 20)]];
+/// let result: Vec<_> = goal.query((x, y)).collect();
+/// assert_eq!(result, vec![(2, 20)]);
+/// ```
+#[macro_export]
+macro_rules! condu {
+    ([$cond:expr $(, $goal:expr)* $(,)?] $(, [$($rest:tt)*])* $(,)?) => {
+        canrun::goals::ifte(
+            canrun::goals::once($cond),
+            canrun::goals::Goal::all(vec![$($goal),*]),
+            canrun::condu!($([$($rest)*]),*),
+        )
+    };
+    () => {
+        canrun::goals::Goal::fail()
+    };
+}
+pub use condu;
+
+#[cfg(test)]
+mod tests {
+    use super::ifte;
+    use crate as canrun;
+    use crate::budget::{Budget, Budgeted};
+    use crate::example::I32;
+    use crate::search::{DepthFirst, Interleave};
+    use crate::{all, any, either, lazy, lt, unify, util, var, Goal};
+
+    fn endless<'a>() -> Goal<'a, I32> {
+        lazy(|| either(Goal::fail(), endless()))
+    }
+
+    #[test]
+    fn runs_then_on_every_solution() {
+        let (x, y) = (var(), var());
+        let goal: Goal<I32> = ifte(any![unify(x, 1), unify(x, 2)], unify(y, x), unify(y, 0));
+        let results: Vec<_> = goal.query((x, y)).collect();
+        assert_eq!(results, vec![(1, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn runs_otherwise_without_solutions() {
+        let (x, y) = (var(), var());
+        let goals: Vec<Goal<I32>> = vec![
+            unify(x, 3),
+            ifte(any![unify(x, 1), unify(x, 2)], unify(y, 1), unify(y, 0)),
+        ];
+        util::assert_permutations_resolve_to(goals, y, vec![0]);
+    }
+
+    #[test]
+    fn fails_when_then_fails() {
+        let x = var();
+        let goal: Goal<I32> = ifte(unify(x, 1), unify(x, 2), unify(x, 3));
+        assert!(util::goal_resolves_to(goal, x).is_empty());
+    }
+
+    #[test]
+    fn conditions_with_unresolved_constraints_count() {
+        let (x, y) = (var(), var());
+        let goals: Vec<Goal<I32>> = vec![ifte(lt(x, y), unify(x, 1), unify(x, 5)), unify(y, 3)];
+        util::assert_permutations_resolve_to(goals, x, vec![1]);
+    }
+
+    #[test]
+    fn leaves_other_forks_alone() {
+        let (x, y) = (var(), var());
+        let goal: Goal<I32> = all![
+            either(unify(y, 1), unify(y, 2)),
+            ifte(unify(x, 1), Goal::succeed(), Goal::fail()),
+        ];
+        let results: Vec<_> = goal.query((x, y)).collect();
+        assert_eq!(results, vec![(1, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn conda_commits_to_the_first_clause() {
+        let (x, y) = (var(), var());
+        let goal: Goal<I32> = all![
+            any![unify(x, 2), unify(x, 3)],
+            conda![
+                [unify(x, 1), unify(y, 10)],
+                [any![unify(x, 2), unify(x, 3)], unify(y, 20)],
+                [Goal::succeed(), unify(y, 30)],
+            ],
+        ];
+        let results: Vec<_> = goal.query((x, y)).collect();
+        assert_eq!(results, vec![(2, 20), (3, 20)]);
+
+        let goal: Goal<I32> = conda![[unify(x, 1), unify(x, 2)], [Goal::succeed()]];
+        assert!(util::goal_resolves_to(goal, x).is_empty());
+
+        let goal: Goal<I32> = conda![];
+        assert!(!goal.has_solution());
+    }
+
+    #[test]
+    fn condu_keeps_one_solution() {
+        let (x, y) = (var(), var());
+        let goal: Goal<I32> = condu![
+            [unify(x, 4), unify(y, 0)],
+            [any![unify(x, 1), unify(x, 2)], unify(y, x)],
+        ];
+        let results: Vec<_> = goal.query((x, y)).collect();
+        assert_eq!(results, vec![(4, 0)]);
+
+        let goal: Goal<I32> = all![
+            unify(y, 2),
+            condu![[any![unify(x, 1), unify(x, 2)], unify(y, x)]],
+        ];
+        assert!(util::goal_resolves_to(goal, x).is_empty());
+    }
+
+    #[test]
+    fn an_interrupted_cond_is_not_a_failure() {
+        let x = var();
+        let budget = Budget::new().max_steps(100);
+        let goal: Goal<I32> = all![unify(x, 1), ifte(endless(), Goal::fail(), Goal::succeed())];
+        let results = goal.query_with(Budgeted::new(DepthFirst, &budget), x);
+        assert_eq!(results.count(), 0);
+        assert!(budget.interrupted().is_some());
+    }
+
+    #[test]
+    fn cond_is_searched_depth_first() {
+        let x = var();
+        let budget = Budget::new().max_steps(1000);
+        let goal: Goal<I32> = ifte(either(endless(), unify(x, 1)), Goal::succeed(), unify(x, 2));
+        let results: Vec<_> = goal
+            .query_with(Budgeted::new(Interleave, &budget), x)
+            .collect();
+        assert!(results.is_empty());
+        assert!(budget.interrupted().is_some());
+
+        // The same condition on its own is found by an interleaved search.
+        let budget = Budget::new().max_steps(1000);
+        let goal: Goal<I32> = either(endless(), unify(x, 1));
+        let results: Vec<_> = goal
+            .query_with(Budgeted::new(Interleave, &budget), x)
+            .take(1)
+            .collect();
+        assert_eq!(results, vec![1]);
+    }
+}
@@ -1,21 +1,28 @@
 use super::{Goal, GoalEnum};
 use crate::domains::Domain;
+use crate::shared::{Rc, Shareable};
 use crate::state::State;
 use std::fmt;
-use std::rc::Rc;
+
+type LazyFn<'a, D> = dyn_fn!(Fn() -> Goal<'a, D> + 'a);
 
 #[derive(Clone)]
-pub struct Lazy<'a, D: Domain<'a>>(Rc<dyn Fn() -> Goal<'a, D> + 'a>);
+pub struct Lazy<'a, D: Domain<'a>>(Rc<LazyFn<'a, D>>, Option<&'static str>);
 
 impl<'a, D: Domain<'a>> Lazy<'a, D> {
     pub(crate) fn run(self, state: State<'a, D>) -> Option<State<'a, D>>
     where
         D: Domain<'a>,
     {
+        state.step()?;
         let func = self.0;
         let goal = func();
         goal.apply(state)
     }
+
+    pub(super) fn label(&self) -> Option<&'static str> {
+        self.1
+    }
 }
 
 /// Create a [goal](crate::goals::Goal) that is generated via callback just as
@@ -41,20 +48,47 @@ impl<'a, D: Domain<'a>> Lazy<'a, D> {
 pub fn lazy<'a, D, F>(func: F) -> Goal<'a, D>
 where
     D: Domain<'a>,
-    F: Fn() -> Goal<'a, D> + 'a,
+    F: Fn() -> Goal<'a, D> + Shareable + 'a,
 {
-    Goal(GoalEnum::Lazy(Lazy(Rc::new(func))))
+    Goal(GoalEnum::Lazy(Lazy(Rc::new(func), None)))
+}
+
+/// Create a [`lazy`] goal with a label.
+///
+/// The label is shown in the `Debug` output and in
+/// [`Goal::to_dot()`](crate::goals::Goal::to_dot()) in place of the callback,
+/// which can't be inspected.
+///
+/// # Examples
+///
+/// ```
+/// use canrun::{Goal, lazy_labeled, unify, var};
+/// use canrun::example::I32;
+///
+/// let x = var();
+/// let goal: Goal<I32> = lazy_labeled("x is 1", move || unify(x, 1));
+/// assert_eq!(format!("{:?}", goal), "Goal(Lazy(Lazy x is 1))");
+/// ```
+pub fn lazy_labeled<'a, D, F>(label: &'static str, func: F) -> Goal<'a, D>
+where
+    D: Domain<'a>,
+    F: Fn() -> Goal<'a, D> + Shareable + 'a,
+{
+    Goal(GoalEnum::Lazy(Lazy(Rc::new(func), Some(label))))
 }
 
 impl<'a, D: Domain<'a>> fmt::Debug for Lazy<'a, D> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Lazy ??")
+        match self.1 {
+            Some(label) => write!(f, "Lazy {}", label),
+            None => write!(f, "Lazy ??"),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::lazy;
+    use super::{lazy, lazy_labeled};
     use crate::example::I32;
     use crate::goals::unify::unify;
     use crate::goals::Goal;
@@ -68,4 +102,13 @@ mod tests {
         let results = util::goal_resolves_to(goal, x);
         assert_eq!(results, vec![1]);
     }
+
+    #[test]
+    fn labeled_succeeds() {
+        let x = var();
+        let goal: Goal<I32> = lazy_labeled("x", move || unify(x, 1));
+        assert_eq!(format!("{:?}", goal), "Goal(Lazy(Lazy x))");
+        let results = util::goal_resolves_to(goal, x);
+        assert_eq!(results, vec![1]);
+    }
 }
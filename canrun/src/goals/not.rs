@@ -0,0 +1,202 @@
+use super::Goal;
+use crate::domains::Domain;
+use crate::state::constraints::{Constraint, ResolveFn, VarWatch};
+use crate::state::search::{DepthFirst, SearchStrategy};
+use crate::state::State;
+use crate::value::next_id;
+
+/// Create a [goal](crate::goals::Goal) that succeeds only if the inner goal
+/// has no solutions.
+///
+/// This is negation as failure, kept sound by waiting until the answer can't
+/// change. The inner goal is run against the current state, and:
+/// - If it fails, the `not` succeeds.
+/// - If it has a solution without binding any existing variables, the `not`
+///   fails.
+/// - Otherwise its solutions depend on variables that are not resolved yet,
+///   so the `not` waits as a [constraint](crate::state::constraints) and
+///   tries again once one of them is bound.
+///
+/// A `not` that is still waiting when the search ends keeps the state from
+/// being a solution, just like any other unresolved constraint.
+///
+/// Variables created while the inner goal runs, such as inside a
+/// [`lazy`](crate::goals::lazy()) goal, are treated as local to it: "there
+/// is no `y` such that...". Variables created ahead of time are treated as
+/// coming from outside, even if they are only used in the inner goal.
+///
+/// The inner goal is searched exhaustively each time it is attempted, so it
+/// should have a finite number of solutions.
+///
+/// # Example:
+/// ```
+/// use canrun::{Goal, all, any, not, unify, var};
+/// use canrun::example::I32;
+///
+/// let (x, y) = (var(), var());
+/// let goal: Goal<I32> = all![
+///     any![unify(x, 1), unify(x, 2), unify(x, 3)],
+///     not(any![unify(x, y), unify(x, 3)]),
+///     unify(y, 2),
+/// ];
+/// let result: Vec<_> = goal.query(x).collect();
+/// assert_eq!(result, vec![1])
+/// ```
+pub fn not<'a, D>(goal: Goal<'a, D>) -> Goal<'a, D>
+where
+    D: Domain<'a> + 'a,
+{
+    Goal::constraint(Not { goal })
+}
+
+#[derive(Debug)]
+struct Not<'a, D: Domain<'a>> {
+    goal: Goal<'a, D>,
+}
+
+impl<'a, D> Constraint<'a, D> for Not<'a, D>
+where
+    D: Domain<'a> + 'a,
+{
+    fn attempt(&self, state: &State<'a, D>) -> Result<ResolveFn<'a, D>, VarWatch> {
+        let probe = state.clone().log_bindings(next_id());
+        let probe = match probe.and_then(|probe| self.goal.clone().apply(probe)) {
+            Some(probe) => probe,
+            None => return Ok(no_solutions(state)),
+        };
+        let mut watch = Vec::new();
+        for settled in DepthFirst.search(probe) {
+            let (vars, constrained) = settled.touched_since(state);
+            if vars.is_empty() {
+                // Constraints that only involve local variables can never be
+                // resolved, so that branch isn't a solution after all.
+                if !constrained {
+                    return Ok(Box::new(|_| None));
+                }
+            }
+            for var in vars {
+                if !watch.contains(&var) {
+                    watch.push(var);
+                }
+            }
+        }
+        if watch.is_empty() {
+            Ok(no_solutions(state))
+        } else {
+            Err(VarWatch::any_of(watch))
+        }
+    }
+}
+
+// The probe came up empty, which only means the goal has no solutions if the
+// search wasn't cut short by the budget.
+fn no_solutions<'a, D: Domain<'a> + 'a>(state: &State<'a, D>) -> ResolveFn<'a, D> {
+    if state.interrupted() {
+        Box::new(|_| None)
+    } else {
+        Box::new(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::not;
+    use crate as canrun;
+    use crate::budget::Budget;
+    use crate::example::{Collections, I32};
+    use crate::{all, any, assert_1, either, lazy, lt, lvec, unify, util, var, Goal};
+
+    #[test]
+    fn fails_when_the_goal_succeeds() {
+        let x = var();
+        let goals: Vec<Goal<I32>> = vec![unify(x, 1), not(unify(x, 1))];
+        util::assert_permutations_resolve_to(goals, x, vec![]);
+    }
+
+    #[test]
+    fn succeeds_when_the_goal_fails() {
+        let x = var();
+        let goals: Vec<Goal<I32>> = vec![unify(x, 1), not(unify(x, 2))];
+        util::assert_permutations_resolve_to(goals, x, vec![1]);
+    }
+
+    #[test]
+    fn waits_for_unbound_variables() {
+        let x = var();
+        let goals: Vec<Goal<I32>> = vec![either(unify(x, 1), unify(x, 2)), not(unify(x, 1))];
+        util::assert_permutations_resolve_to(goals, x, vec![2]);
+
+        // Nothing ever binds `x`, so the `not` can't be settled.
+        let goal: Goal<I32> = not(unify(x, 1));
+        assert!(!goal.has_solution());
+    }
+
+    #[test]
+    fn waits_on_constraints_in_the_goal() {
+        let (x, y) = (var(), var());
+        let goals: Vec<Goal<I32>> = vec![not(lt(x, y)), unify(x, 2), unify(y, 1)];
+        util::assert_permutations_resolve_to(goals, x, vec![2]);
+
+        let goals: Vec<Goal<I32>> = vec![not(lt(x, y)), unify(x, 1), unify(y, 2)];
+        util::assert_permutations_resolve_to(goals, x, vec![]);
+    }
+
+    #[test]
+    fn local_variables_are_existential() {
+        let (x, xs) = (var(), var());
+        // There is no `y` that pairs up with `x` in `xs`.
+        let no_match = move || {
+            not(lazy(move || {
+                let y = var::<i32>();
+                unify(xs, lvec![x, y])
+            }))
+        };
+        let goals: Vec<Goal<Collections>> = vec![unify(xs, lvec![1, 2]), unify(x, 1), no_match()];
+        util::assert_permutations_resolve_to(goals, x, vec![]);
+
+        let goals: Vec<Goal<Collections>> = vec![unify(xs, lvec![1, 2]), unify(x, 3), no_match()];
+        util::assert_permutations_resolve_to(goals, x, vec![3]);
+    }
+
+    #[test]
+    fn nested() {
+        let x = var();
+        let goal: Goal<I32> = all![
+            any![unify(x, 1), unify(x, 2), unify(x, 3)],
+            not(not(any![unify(x, 1), unify(x, 3)])),
+        ];
+        assert_eq!(goal.query(x).collect::<Vec<_>>(), vec![1, 3]);
+    }
+
+    #[test]
+    fn woken_while_propagating() {
+        let (w, w2, x) = (var(), var(), var());
+        let goals: Vec<Goal<I32>> = vec![
+            not(lazy(move || {
+                let z = var();
+                all![unify(w, 1), assert_1(z, |z| *z > 5), unify(z, w2)]
+            })),
+            unify(w, 1),
+            unify(w2, 1),
+            unify(x, 1),
+        ];
+        util::assert_permutations_resolve_to(goals, x, vec![1]);
+    }
+
+    #[test]
+    fn an_interrupted_probe_is_not_a_failure() {
+        for steps in 1..10 {
+            let x = var();
+            let goal: Goal<I32> = either(
+                all![
+                    unify(x, 1),
+                    not(all![unify(x, 1), unify(x, 1), unify(x, 1), unify(x, 1)])
+                ],
+                unify(x, 2),
+            );
+            let budget = Budget::new().max_steps(steps);
+            let results: Vec<_> = goal.query_bounded(&budget, x).collect();
+            assert!(results.iter().all(|x| *x == 2), "{} steps", steps);
+        }
+    }
+}
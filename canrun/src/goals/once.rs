@@ -0,0 +1,94 @@
+use super::Goal;
+use crate::domains::Domain;
+use crate::state::{Fork, State, StateIter};
+
+#[derive(Debug)]
+struct Once<'a, D: Domain<'a>> {
+    goal: Goal<'a, D>,
+}
+
+impl<'a, D> Fork<'a, D> for Once<'a, D>
+where
+    D: Domain<'a> + 'a,
+{
+    fn fork(&self, state: State<'a, D>) -> StateIter<'a, D> {
+        Box::new(state.search_own_forks(self.goal.clone()).take(1))
+    }
+}
+
+/// Create a [goal](crate::goals::Goal) that keeps only the first solution of
+/// another goal.
+///
+/// The alternatives of the inner goal are searched depth first until one
+/// succeeds, and the rest are never tried. Any other pending alternatives in
+/// the state are left alone. Like the other forking goals, this is only
+/// evaluated once the rest of the goals in the state have been applied, and
+/// since it has no [`size_hint`](crate::state::Fork::size_hint()) it also
+/// waits for any sibling forks that do.
+///
+/// # Example:
+/// ```
+/// use canrun::{Goal, any, once, unify, var};
+/// use canrun::example::I32;
+///
+/// let x = var();
+/// let goal: Goal<I32> = once(any![unify(x, 1), unify(x, 2), unify(x, 3)]);
+/// let result: Vec<_> = goal.query(x).collect();
+/// assert_eq!(result, vec![1])
+/// ```
+pub fn once<'a, D>(goal: Goal<'a, D>) -> Goal<'a, D>
+where
+    D: Domain<'a> + 'a,
+{
+    Goal::fork(Once { goal })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::once;
+    use crate as canrun;
+    use crate::example::I32;
+    use crate::{all, any, either, unify, util, var, Goal};
+
+    #[test]
+    fn keeps_the_first_solution() {
+        let x = var();
+        let goal: Goal<I32> = once(any![unify(x, 1), unify(x, 2)]);
+        assert_eq!(util::goal_resolves_to(goal, x), vec![1]);
+    }
+
+    #[test]
+    fn sees_the_rest_of_the_state() {
+        let x = var();
+        let goals: Vec<Goal<I32>> = vec![once(any![unify(x, 1), unify(x, 2)]), unify(x, 2)];
+        util::assert_permutations_resolve_to(goals, x, vec![2]);
+    }
+
+    #[test]
+    fn waits_for_sibling_forks() {
+        let x = var();
+        let goals: Vec<Goal<I32>> = vec![
+            either(unify(x, 2), unify(x, 3)),
+            once(any![unify(x, 1), unify(x, 2)]),
+        ];
+        util::assert_permutations_resolve_to(goals, x, vec![2]);
+    }
+
+    #[test]
+    fn leaves_other_forks_alone() {
+        let (x, y) = (var(), var());
+        let goal: Goal<I32> = all![
+            any![unify(y, 1), unify(y, 2)],
+            once(any![unify(x, 1), unify(x, 2)]),
+        ];
+        let results: Vec<_> = goal.query((x, y)).collect();
+        assert_eq!(results, vec![(1, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn fails_with_the_goal() {
+        let x = var();
+        let goal: Goal<I32> = once(any![unify(1, 2), unify(3, 4)]);
+        assert!(util::goal_resolves_to(goal, x).is_empty());
+    }
+}
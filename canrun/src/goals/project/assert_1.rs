@@ -1,15 +1,15 @@
 use crate::domains::DomainType;
 use crate::goals::Goal;
+use crate::shared::{Rc, Shareable};
 use crate::state::constraints::{resolve_1, Constraint, ResolveFn, VarWatch};
 use crate::state::State;
 use crate::value::{IntoVal, Val};
 use std::fmt;
 use std::fmt::Debug;
-use std::rc::Rc;
 
 pub struct Assert1<'a, A: Debug> {
     a: Val<A>,
-    f: Rc<dyn Fn(&A) -> bool + 'a>,
+    f: Rc<dyn_fn!(Fn(&A) -> bool + 'a)>,
 }
 
 /// Create a [projection goal](super) that succeeds if the resolved value passes
@@ -26,10 +26,10 @@ pub struct Assert1<'a, A: Debug> {
 /// ```
 pub fn assert_1<'a, A, AV, D, F>(a: AV, func: F) -> Goal<'a, D>
 where
-    A: Debug + 'a,
+    A: Debug + Shareable + 'a,
     AV: IntoVal<A>,
     D: DomainType<'a, A>,
-    F: Fn(&A) -> bool + 'a,
+    F: Fn(&A) -> bool + Shareable + 'a,
 {
     Goal::constraint(Assert1 {
         a: a.into_val(),
@@ -39,7 +39,7 @@ where
 
 impl<'a, A, Dom> Constraint<'a, Dom> for Assert1<'a, A>
 where
-    A: Debug + 'a,
+    A: Debug + Shareable + 'a,
     Dom: DomainType<'a, A>,
 {
     fn attempt(&self, state: &State<'a, Dom>) -> Result<ResolveFn<'a, Dom>, VarWatch> {
@@ -1,16 +1,16 @@
 use crate::domains::DomainType;
 use crate::goals::Goal;
+use crate::shared::{Rc, Shareable};
 use crate::state::constraints::{resolve_2, Constraint, ResolveFn, VarWatch};
 use crate::state::State;
 use crate::value::{IntoVal, Val};
 use std::fmt;
 use std::fmt::Debug;
-use std::rc::Rc;
 
 pub struct Assert2<'a, A: Debug, B: Debug> {
     a: Val<A>,
     b: Val<B>,
-    f: Rc<dyn Fn(&A, &B) -> bool + 'a>,
+    f: Rc<dyn_fn!(Fn(&A, &B) -> bool + 'a)>,
 }
 
 /// Create a [projection goal](super) that succeeds if the resolved values pass
@@ -31,12 +31,12 @@ pub struct Assert2<'a, A: Debug, B: Debug> {
 /// ```
 pub fn assert_2<'a, A, AV, B, BV, D, F>(a: AV, b: BV, func: F) -> Goal<'a, D>
 where
-    A: Debug + 'a,
+    A: Debug + Shareable + 'a,
     AV: IntoVal<A>,
-    B: Debug + 'a,
+    B: Debug + Shareable + 'a,
     BV: IntoVal<B>,
     D: DomainType<'a, A> + DomainType<'a, B>,
-    F: Fn(&A, &B) -> bool + 'a,
+    F: Fn(&A, &B) -> bool + Shareable + 'a,
 {
     Goal::constraint(Assert2 {
         a: a.into_val(),
@@ -47,8 +47,8 @@ where
 
 impl<'a, A, B, Dom> Constraint<'a, Dom> for Assert2<'a, A, B>
 where
-    A: Debug + 'a,
-    B: Debug + 'a,
+    A: Debug + Shareable + 'a,
+    B: Debug + Shareable + 'a,
     Dom: DomainType<'a, A> + DomainType<'a, B>,
 {
     fn attempt(&self, state: &State<'a, Dom>) -> Result<ResolveFn<'a, Dom>, VarWatch> {
@@ -1,4 +1,5 @@
 use crate::goals::Goal;
+use crate::shared::{Rc, Shareable};
 use crate::state::constraints::{Constraint, OneOfTwo, ResolveFn, VarWatch};
 use crate::DomainType;
 use crate::State;
@@ -6,7 +7,6 @@ use crate::UnifyIn;
 use crate::{IntoVal, Val};
 use std::fmt;
 use std::fmt::Debug;
-use std::rc::Rc;
 
 /// Create a [projection goal](super) that allows deriving one resolved value
 /// from the other.
@@ -38,8 +38,8 @@ where
     AV: IntoVal<A>,
     BV: IntoVal<B>,
     D: DomainType<'a, A> + DomainType<'a, B>,
-    AtoB: Fn(&A) -> B + 'a,
-    BtoA: Fn(&B) -> A + 'a,
+    AtoB: Fn(&A) -> B + Shareable + 'a,
+    BtoA: Fn(&B) -> A + Shareable + 'a,
 {
     Goal::constraint(Map1 {
         a: a.into_val(),
@@ -52,8 +52,8 @@ where
 pub struct Map1<'a, A: Debug, B: Debug> {
     a: Val<A>,
     b: Val<B>,
-    a_to_b: Rc<dyn Fn(&A) -> B + 'a>,
-    b_to_a: Rc<dyn Fn(&B) -> A + 'a>,
+    a_to_b: Rc<dyn_fn!(Fn(&A) -> B + 'a)>,
+    b_to_a: Rc<dyn_fn!(Fn(&B) -> A + 'a)>,
 }
 
 impl<'a, A: Debug, B: Debug> Debug for Map1<'a, A, B> {
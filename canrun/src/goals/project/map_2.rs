@@ -1,4 +1,5 @@
 use crate::goals::{Goal, GoalEnum};
+use crate::shared::{Rc, Shareable};
 use crate::state::constraints::{Constraint, ResolveFn, TwoOfThree, VarWatch};
 use crate::state::State;
 use crate::value::{IntoVal, Val};
@@ -6,7 +7,6 @@ use crate::DomainType;
 use crate::UnifyIn;
 use std::fmt;
 use std::fmt::Debug;
-use std::rc::Rc;
 
 /// Create a [projection goal](super) that allows deriving one resolved value
 /// from the other two.
@@ -43,9 +43,9 @@ where
     C: UnifyIn<'a, D> + Debug + 'a,
     CV: IntoVal<C>,
     D: DomainType<'a, A> + DomainType<'a, B> + DomainType<'a, C>,
-    ABtoC: Fn(&A, &B) -> C + 'a,
-    ACtoB: Fn(&A, &C) -> B + 'a,
-    BCtoA: Fn(&B, &C) -> A + 'a,
+    ABtoC: Fn(&A, &B) -> C + Shareable + 'a,
+    ACtoB: Fn(&A, &C) -> B + Shareable + 'a,
+    BCtoA: Fn(&B, &C) -> A + Shareable + 'a,
 {
     Goal(GoalEnum::Constraint(Rc::new(Map2 {
         a: a.into_val(),
@@ -61,9 +61,9 @@ pub struct Map2<'a, A: Debug, B: Debug, C: Debug> {
     a: Val<A>,
     b: Val<B>,
     c: Val<C>,
-    ab_to_c: Rc<dyn Fn(&A, &B) -> C + 'a>,
-    ac_to_b: Rc<dyn Fn(&A, &C) -> B + 'a>,
-    bc_to_a: Rc<dyn Fn(&B, &C) -> A + 'a>,
+    ab_to_c: Rc<dyn_fn!(Fn(&A, &B) -> C + 'a)>,
+    ac_to_b: Rc<dyn_fn!(Fn(&A, &C) -> B + 'a)>,
+    bc_to_a: Rc<dyn_fn!(Fn(&B, &C) -> A + 'a)>,
 }
 
 impl<'a, A: Debug, B: Debug, C: Debug> Debug for Map2<'a, A, B, C> {
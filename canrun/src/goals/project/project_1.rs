@@ -1,14 +1,14 @@
 use crate::domains::DomainType;
+use crate::shared::{Rc, Shareable};
 use crate::state::constraints::{resolve_1, Constraint, ResolveFn, VarWatch};
 use crate::value::{IntoVal, Val};
 use crate::{Goal, State};
 use std::fmt;
 use std::fmt::Debug;
-use std::rc::Rc;
 
 pub struct Project1<'a, A: Debug, D: DomainType<'a, A>> {
     a: Val<A>,
-    f: Rc<dyn Fn(&A) -> Goal<'a, D> + 'a>,
+    f: Rc<dyn_fn!(Fn(&A) -> Goal<'a, D> + 'a)>,
 }
 
 /// Create a [projection goal](super) that allows creating a new goal based on
@@ -25,10 +25,10 @@ pub struct Project1<'a, A: Debug, D: DomainType<'a, A>> {
 /// ```
 pub fn project_1<'a, A, AV, D, F>(a: AV, func: F) -> Goal<'a, D>
 where
-    A: Debug + 'a,
+    A: Debug + Shareable + 'a,
     AV: IntoVal<A>,
     D: DomainType<'a, A>,
-    F: Fn(&A) -> Goal<'a, D> + 'a,
+    F: Fn(&A) -> Goal<'a, D> + Shareable + 'a,
 {
     Goal::constraint(Project1 {
         a: a.into_val(),
@@ -38,7 +38,7 @@ where
 
 impl<'a, A, Dom> Constraint<'a, Dom> for Project1<'a, A, Dom>
 where
-    A: Debug,
+    A: Debug + Shareable,
     Dom: DomainType<'a, A>,
 {
     fn attempt(&self, state: &State<'a, Dom>) -> Result<ResolveFn<'a, Dom>, VarWatch> {
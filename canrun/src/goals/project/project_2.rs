@@ -1,20 +1,20 @@
 use crate::domains::DomainType;
+use crate::shared::{Rc, Shareable};
 use crate::state::constraints::{resolve_2, Constraint, ResolveFn, VarWatch};
 use crate::value::{IntoVal, Val};
 use crate::{Goal, State};
 use std::fmt;
 use std::fmt::Debug;
-use std::rc::Rc;
 
 pub struct Project2<'a, A, B, D>
 where
-    A: Debug,
-    B: Debug,
+    A: Debug + Shareable,
+    B: Debug + Shareable,
     D: DomainType<'a, A> + DomainType<'a, B>,
 {
     a: Val<A>,
     b: Val<B>,
-    f: Rc<dyn Fn(Rc<A>, Rc<B>) -> Goal<'a, D> + 'a>,
+    f: Rc<dyn_fn!(Fn(Rc<A>, Rc<B>) -> Goal<'a, D> + 'a)>,
 }
 
 /// Create a [projection goal](super) that allows creating a new goal based on
@@ -35,12 +35,12 @@ where
 /// ```
 pub fn project_2<'a, A, AV, B, BV, D, F>(a: AV, b: BV, func: F) -> Goal<'a, D>
 where
-    A: Debug + 'a,
+    A: Debug + Shareable + 'a,
     AV: IntoVal<A>,
-    B: Debug + 'a,
+    B: Debug + Shareable + 'a,
     BV: IntoVal<B>,
     D: DomainType<'a, A> + DomainType<'a, B>,
-    F: Fn(Rc<A>, Rc<B>) -> Goal<'a, D> + 'a,
+    F: Fn(Rc<A>, Rc<B>) -> Goal<'a, D> + Shareable + 'a,
 {
     Goal::constraint(Project2 {
         a: a.into_val(),
@@ -51,8 +51,8 @@ where
 
 impl<'a, A, B, Dom> Constraint<'a, Dom> for Project2<'a, A, B, Dom>
 where
-    A: Debug,
-    B: Debug,
+    A: Debug + Shareable,
+    B: Debug + Shareable,
     Dom: DomainType<'a, A> + DomainType<'a, B>,
 {
     fn attempt(&self, state: &State<'a, Dom>) -> Result<ResolveFn<'a, Dom>, VarWatch> {
@@ -64,8 +64,8 @@ where
 
 impl<'a, A, B, D> Debug for Project2<'a, A, B, D>
 where
-    A: Debug,
-    B: Debug,
+    A: Debug + Shareable,
+    B: Debug + Shareable,
     D: DomainType<'a, A> + DomainType<'a, B>,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
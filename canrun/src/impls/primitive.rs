@@ -1,5 +1,5 @@
-use crate::{Domain, DomainType, ReifyIn, ResolvedState, State, UnifyIn};
-use std::rc::Rc;
+use crate::shared::Rc;
+use crate::{Domain, DomainType, FreshVars, ReifyFresh, ReifyIn, ResolvedState, State, UnifyIn};
 
 macro_rules! impl_unify_eq {
     ($($type:ty),+) => {
@@ -26,6 +26,13 @@ macro_rules! impl_reify_copy {
                     Some(*self)
                 }
             }
+
+            impl<'a, D: Domain<'a>> ReifyFresh<'a, D> for $type {
+                type Reified = $type;
+                fn reify_fresh(&self, _: &ResolvedState<D>, _: &mut FreshVars) -> $type {
+                    *self
+                }
+            }
         )+
     }
 }
@@ -39,6 +46,13 @@ macro_rules! impl_reify_clone {
                     Some(self.clone())
                 }
             }
+
+            impl<'a, D: Domain<'a>> ReifyFresh<'a, D> for $type {
+                type Reified = $type;
+                fn reify_fresh(&self, _: &ResolvedState<D>, _: &mut FreshVars) -> $type {
+                    self.clone()
+                }
+            }
         )+
     }
 }
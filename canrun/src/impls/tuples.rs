@@ -1,9 +1,9 @@
 use crate::domains::DomainType;
-use crate::state::State;
-use crate::value::{ReifyIn, Val};
-use crate::ResolvedState;
+use crate::shared::Rc;
+use crate::state::{OccursCheck, State};
+use crate::value::{ReifyFresh, ReifyIn, Val};
 use crate::UnifyIn;
-use std::rc::Rc;
+use crate::{FreshVars, ResolvedState};
 
 macro_rules! impl_for_tuple {
     ($($t:ident => $r:ident),+) => {
@@ -28,6 +28,12 @@ macro_rules! impl_for_tuple {
                         $(.unify(&$t, &$r)?)*
                 )
             }
+
+            fn occurs_check(&self, check: &mut OccursCheck<'_, 'a, D>) {
+                #![allow(non_snake_case)]
+                let ($($t),*) = self;
+                $(check.val($t);)*
+            }
         }
 
         impl<'a, D: 'a, $($t: ReifyIn<'a, D, Reified = $r>, $r,)*> ReifyIn<'a, D> for ($($t),*) {
@@ -38,6 +44,15 @@ macro_rules! impl_for_tuple {
                 Some(($($t.reify_in(state)?),*))
             }
         }
+
+        impl<'a, D: 'a, $($t: ReifyFresh<'a, D, Reified = $r>, $r,)*> ReifyFresh<'a, D> for ($($t),*) {
+            type Reified = ($($t::Reified),*);
+            fn reify_fresh(&self, state: &ResolvedState<D>, fresh: &mut FreshVars) -> Self::Reified {
+                #![allow(non_snake_case)]
+                let ($($t),*) = self;
+                ($($t.reify_fresh(state, fresh)),*)
+            }
+        }
     };
 }
 
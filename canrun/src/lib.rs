@@ -23,6 +23,9 @@
 //! assert_eq!(result, vec![1])
 //! ```
 
+#[macro_use]
+pub mod shared;
+
 pub mod collections;
 pub mod domains;
 pub mod example;
@@ -1,6 +1,9 @@
 use crate::domains::Domain;
-use crate::state::IterResolved;
-use crate::ReifyIn;
+use crate::state::budget::{self, BoundedQuery, Budget};
+use crate::state::search::{DepthFirst, Interleave, SearchStrategy};
+use crate::state::{IterResolved, Residual};
+use crate::{FreshVars, ReifyFresh, ReifyIn};
+use std::time::Instant;
 
 /// Derive [reified](crate::value::ReifyIn) [values](crate::value) potential
 /// resolved states.
@@ -63,16 +66,219 @@ pub trait Query<'a, D: Domain<'a> + 'a> {
     fn query<Q>(self, query: Q) -> Box<dyn Iterator<Item = Q::Reified> + 'a>
     where
         Q: ReifyIn<'a, D> + 'a;
+
+    /// Get [reified](crate::value::ReifyIn) results while fairly interleaving
+    /// the branches of every fork.
+    ///
+    /// See
+    /// [`.iter_resolved_interleaved()`](IterResolved::iter_resolved_interleaved())
+    /// for details.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, Query, any, either, lazy, unify, var, LVar};
+    /// use canrun::example::I32;
+    ///
+    /// fn forever<'a>(x: LVar<i32>) -> Goal<'a, I32> {
+    ///     lazy(move || either(forever(x), unify(x, 0)))
+    /// }
+    ///
+    /// let x = var();
+    /// let goal: Goal<I32> = any![forever(x), unify(x, 1)];
+    /// let result: Vec<_> = goal.query_interleaved(x).take(3).collect();
+    /// assert!(result.contains(&1));
+    /// ```
+    fn query_interleaved<Q>(self, query: Q) -> Box<dyn Iterator<Item = Q::Reified> + 'a>
+    where
+        Q: ReifyIn<'a, D> + 'a;
+
+    /// Get [reified](crate::value::ReifyIn) results while exploring forks with
+    /// a specific [search strategy](crate::search).
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, Query, any, unify, var};
+    /// use canrun::search::IterativeDeepening;
+    /// use canrun::example::I32;
+    ///
+    /// let x = var();
+    /// let goal: Goal<I32> = any![any![unify(x, 1), unify(x, 2)], unify(x, 3)];
+    /// let result: Vec<_> = Query::query_with(goal, IterativeDeepening::new(), x).collect();
+    /// assert_eq!(result, vec![3, 1, 2])
+    /// ```
+    fn query_with<S, Q>(self, strategy: S, query: Q) -> Box<dyn Iterator<Item = Q::Reified> + 'a>
+    where
+        S: SearchStrategy<'a, D>,
+        Q: ReifyIn<'a, D> + 'a;
+
+    /// Get [reified](crate::value::ReifyIn) results, giving up once the
+    /// [`Budget`] runs out.
+    ///
+    /// The returned iterator stops early if the budget is exhausted or
+    /// cancelled. Once it has finished,
+    /// [`.interrupted()`](BoundedQuery::interrupted()) tells you whether every
+    /// result was found or why the search was cut short.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, Query, Interrupt, lazy, either, var};
+    /// use canrun::budget::Budget;
+    /// use canrun::example::I32;
+    ///
+    /// fn nowhere<'a>() -> Goal<'a, I32> {
+    ///     lazy(|| either(nowhere(), Goal::fail()))
+    /// }
+    ///
+    /// let x = var();
+    /// let budget = Budget::new().max_steps(1000);
+    /// let mut query = Query::query_bounded(nowhere(), &budget, x);
+    /// assert_eq!(query.next(), None);
+    /// assert_eq!(query.interrupted(), Some(Interrupt::StepLimit));
+    /// ```
+    fn query_bounded<Q>(self, budget: &Budget, query: Q) -> BoundedQuery<'a, Q::Reified>
+    where
+        Q: ReifyIn<'a, D> + 'a;
+
+    /// Get [reified](crate::value::ReifyIn) results until the deadline
+    /// passes.
+    ///
+    /// Results found before the deadline are returned as usual. Once the
+    /// iterator has finished, [`.interrupted()`](BoundedQuery::interrupted())
+    /// returns [`Interrupt::Deadline`](crate::Interrupt::Deadline) if time ran
+    /// out, or `None` if every result was found.
+    ///
+    /// This is a shorthand for [`.query_bounded()`](Query::query_bounded())
+    /// with a [`Budget`] that only has a
+    /// [deadline](crate::budget::Budget::deadline()).
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, Query, any, unify, var};
+    /// use canrun::example::I32;
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let x = var();
+    /// let goal: Goal<I32> = any![unify(x, 1), unify(x, 2)];
+    /// let deadline = Instant::now() + Duration::from_secs(1);
+    /// let mut query = Query::query_until(goal, deadline, x);
+    /// assert_eq!(query.by_ref().collect::<Vec<_>>(), vec![1, 2]);
+    /// assert_eq!(query.interrupted(), None);
+    /// ```
+    fn query_until<Q>(self, deadline: Instant, query: Q) -> BoundedQuery<'a, Q::Reified>
+    where
+        Q: ReifyIn<'a, D> + 'a;
+
+    /// Get [reified](crate::value::ReifyIn) results, including those that
+    /// still have [pending constraints](crate::state::PendingConstraint).
+    ///
+    /// A regular [`.query()`](Query::query()) drops any result where a
+    /// constraint is waiting on variables that were never resolved. This
+    /// returns them instead, along with a description of each pending
+    /// constraint. The [`value`](crate::state::Residual::value) is `None` if
+    /// the query itself depends on unresolved variables.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, Query, lt, var};
+    /// use canrun::example::I32;
+    ///
+    /// let (x, y) = (var(), var());
+    /// let goal: Goal<I32> = lt(x, y);
+    /// let results: Vec<_> = Query::query_residual(goal, x).collect();
+    /// assert_eq!(results.len(), 1);
+    /// assert_eq!(results[0].value, None);
+    /// assert!(results[0].constraints[0].watches(x));
+    /// ```
+    fn query_residual<Q>(self, query: Q) -> Box<dyn Iterator<Item = Residual<Q::Reified>> + 'a>
+    where
+        Q: ReifyIn<'a, D> + 'a;
+
+    /// Get [reified](crate::value::ReifyFresh) results, with any unbound
+    /// variables replaced by [`Reified::Fresh`](crate::Reified::Fresh)
+    /// placeholders.
+    ///
+    /// A regular [`.query()`](Query::query()) drops any result that is not
+    /// fully resolved. Here every resolved state produces a result. Unbound
+    /// variables are numbered separately for each result, and a variable that
+    /// appears more than once in the query is given the same number each
+    /// time.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, Query, Reified, unify, var};
+    /// use canrun::lvec::{lvec, LVec};
+    /// use canrun::example::Collections;
+    ///
+    /// let (x, y) = (var(), var());
+    /// let goal: Goal<Collections> = unify(x, lvec![1, y]);
+    /// let result: Vec<_> = Query::query_fresh(goal, (x, y)).collect();
+    /// assert_eq!(result, vec![(
+    ///     Reified::Value(vec![Reified::Value(1), Reified::Fresh(0)]),
+    ///     Reified::Fresh(0),
+    /// )]);
+    /// ```
+    fn query_fresh<Q>(self, query: Q) -> Box<dyn Iterator<Item = Q::Reified> + 'a>
+    where
+        Q: ReifyFresh<'a, D> + 'a;
 }
 
-impl<'a, D: Domain<'a> + 'a, S: IterResolved<'a, D>> Query<'a, D> for S {
+impl<'a, D: Domain<'a> + 'a, R: IterResolved<'a, D>> Query<'a, D> for R {
     fn query<Q>(self, query: Q) -> Box<dyn Iterator<Item = Q::Reified> + 'a>
     where
         Q: ReifyIn<'a, D> + 'a,
+    {
+        self.query_with(DepthFirst, query)
+    }
+
+    fn query_interleaved<Q>(self, query: Q) -> Box<dyn Iterator<Item = Q::Reified> + 'a>
+    where
+        Q: ReifyIn<'a, D> + 'a,
+    {
+        self.query_with(Interleave, query)
+    }
+
+    fn query_with<S, Q>(self, strategy: S, query: Q) -> Box<dyn Iterator<Item = Q::Reified> + 'a>
+    where
+        S: SearchStrategy<'a, D>,
+        Q: ReifyIn<'a, D> + 'a,
     {
         Box::new(
-            self.iter_resolved()
+            self.iter_resolved_with(strategy)
                 .filter_map(move |resolved| query.reify_in(&resolved)),
         )
     }
+
+    fn query_bounded<Q>(self, budget: &Budget, query: Q) -> BoundedQuery<'a, Q::Reified>
+    where
+        Q: ReifyIn<'a, D> + 'a,
+    {
+        BoundedQuery::new(self.query_with(budget::strategy(budget), query), budget)
+    }
+
+    fn query_until<Q>(self, deadline: Instant, query: Q) -> BoundedQuery<'a, Q::Reified>
+    where
+        Q: ReifyIn<'a, D> + 'a,
+    {
+        self.query_bounded(&Budget::new().deadline(deadline), query)
+    }
+
+    fn query_residual<Q>(self, query: Q) -> Box<dyn Iterator<Item = Residual<Q::Reified>> + 'a>
+    where
+        Q: ReifyIn<'a, D> + 'a,
+    {
+        Box::new(self.iter_residual().map(move |residual| Residual {
+            value: residual.reify(&query),
+            constraints: residual.constraints,
+        }))
+    }
+
+    fn query_fresh<Q>(self, query: Q) -> Box<dyn Iterator<Item = Q::Reified> + 'a>
+    where
+        Q: ReifyFresh<'a, D> + 'a,
+    {
+        Box::new(
+            self.iter_resolved()
+                .map(move |resolved| query.reify_fresh(&resolved, &mut FreshVars::new())),
+        )
+    }
 }
@@ -1,5 +1,7 @@
+use crate::value::LVarId;
 use crate::{Domain, DomainType, IntoVal, LVar, ResolvedState, Val};
-use std::fmt::Debug;
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Display};
 
 /// Extract a fully resolved `T` from a [`Val<T>`](crate::value::Val).
 ///
@@ -98,10 +100,145 @@ where
     }
 }
 
+/// A reified value that may still contain unbound [logic
+/// variables](crate::value::LVar).
+///
+/// Returned from [`ReifyFresh`], typically through
+/// [`.query_fresh()`](crate::Query::query_fresh()). Unbound variables are
+/// numbered in the order they are first encountered, starting at zero. A
+/// variable that appears more than once in the same result is always given
+/// the same number.
+///
+/// The [`Display`] implementation writes unbound variables as `_0`, `_1`, and
+/// so on, in the style of miniKanren.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Reified<T> {
+    /// A resolved value.
+    Value(T),
+    /// An unbound variable.
+    Fresh(usize),
+}
+
+impl<T> Reified<T> {
+    /// Return the resolved value, or `None` if the variable was unbound.
+    pub fn value(self) -> Option<T> {
+        match self {
+            Reified::Value(value) => Some(value),
+            Reified::Fresh(_) => None,
+        }
+    }
+
+    /// Return `true` if the variable was unbound.
+    pub fn is_fresh(&self) -> bool {
+        matches!(self, Reified::Fresh(_))
+    }
+}
+
+impl<T: Display> Display for Reified<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reified::Value(value) => value.fmt(f),
+            Reified::Fresh(n) => write!(f, "_{}", n),
+        }
+    }
+}
+
+/// Keeps track of the numbers given to unbound variables while reifying a
+/// single result with [`ReifyFresh`].
+#[derive(Debug, Default)]
+pub struct FreshVars {
+    names: HashMap<LVarId, usize>,
+}
+
+impl FreshVars {
+    /// Create an empty set of fresh variable names.
+    pub fn new() -> Self {
+        FreshVars::default()
+    }
+
+    fn name<T>(&mut self, var: &LVar<T>) -> usize {
+        let next = self.names.len();
+        *self.names.entry(var.id).or_insert(next)
+    }
+}
+
+/// Extract a `T` from a [`Val<T>`](crate::value::Val), replacing any unbound
+/// variables with [`Reified::Fresh`] placeholders.
+///
+/// Where [`ReifyIn`] gives up on values that are not fully resolved, this
+/// always succeeds. Every [`Val`] in `Self` becomes a [`Reified`] in the
+/// result.
+///
+/// # Example:
+/// ```
+/// use canrun::{IterResolved, ReifyFresh, FreshVars, Reified, ResolvedState, State, Val, val, var};
+/// use canrun::example::TupleI32;
+///
+/// State::new()
+///     .iter_resolved()
+///     .for_each(|state: ResolvedState<TupleI32>| {
+///         let x = var();
+///         let y: (Val<i32>, Val<i32>, Val<i32>) = (val!(x), val!(2), val!(x));
+///         assert_eq!(
+///             y.reify_fresh(&state, &mut FreshVars::new()),
+///             (Reified::Fresh(0), Reified::Value(2), Reified::Fresh(0))
+///         );
+///     });
+/// ```
+pub trait ReifyFresh<'a, D>: Sized {
+    /// The "concrete" type that `Self` reifies to.
+    type Reified;
+
+    /// Extract a reified `Self` from a compatible
+    /// [`ResolvedState`](crate::state::ResolvedState), naming unbound
+    /// variables with `fresh`. This trait is usually used indirectly through
+    /// [`.query_fresh()`](crate::Query::query_fresh()).
+    fn reify_fresh(&self, state: &ResolvedState<D>, fresh: &mut FreshVars) -> Self::Reified;
+}
+
+impl<'a, T, D> ReifyFresh<'a, D> for LVar<T>
+where
+    T: ReifyFresh<'a, D> + Debug,
+    D: DomainType<'a, T> + 'a,
+{
+    type Reified = Reified<T::Reified>;
+    fn reify_fresh(&self, state: &ResolvedState<D>, fresh: &mut FreshVars) -> Self::Reified {
+        self.into_val().reify_fresh(state, fresh)
+    }
+}
+
+impl<'a, T, D> ReifyFresh<'a, D> for Val<T>
+where
+    T: ReifyFresh<'a, D> + Debug,
+    D: DomainType<'a, T> + 'a,
+{
+    type Reified = Reified<T::Reified>;
+    fn reify_fresh(&self, state: &ResolvedState<D>, fresh: &mut FreshVars) -> Self::Reified {
+        match state.resolve_val(self) {
+            Val::Resolved(resolved) => Reified::Value(resolved.reify_fresh(state, fresh)),
+            Val::Var(var) => Reified::Fresh(fresh.name(var)),
+        }
+    }
+}
+
+impl<'a, T, D> ReifyFresh<'a, D> for &T
+where
+    T: ReifyFresh<'a, D>,
+    D: Domain<'a> + 'a,
+{
+    type Reified = T::Reified;
+    fn reify_fresh(&self, state: &ResolvedState<D>, fresh: &mut FreshVars) -> Self::Reified {
+        (*self).reify_fresh(state, fresh)
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::example::TupleI32;
     use crate::example::I32;
-    use crate::{val, var, IterResolved, ReifyIn, ResolvedState, State, Val};
+    use crate::{
+        val, var, FreshVars, IterResolved, Reified, ReifyFresh, ReifyIn, ResolvedState, State, Val,
+    };
 
     #[test]
     fn reify_var() {
@@ -122,4 +259,50 @@ mod tests {
                 assert_eq!(x.reify_in(&state), Some(1));
             });
     }
+
+    #[test]
+    fn reify_fresh_var() {
+        let x: Val<i32> = val!(var());
+        State::new()
+            .iter_resolved()
+            .for_each(|state: ResolvedState<I32>| {
+                assert_eq!(
+                    x.reify_fresh(&state, &mut FreshVars::new()),
+                    Reified::Fresh(0)
+                );
+            });
+    }
+
+    #[test]
+    fn reify_fresh_resolved() {
+        let x = val!(1);
+        State::new()
+            .iter_resolved()
+            .for_each(|state: ResolvedState<I32>| {
+                assert_eq!(
+                    x.reify_fresh(&state, &mut FreshVars::new()),
+                    Reified::Value(1)
+                );
+            });
+    }
+
+    #[test]
+    fn reify_fresh_numbers_vars_in_order() {
+        let (x, y) = (var(), var());
+        let tuple: (Val<i32>, Val<i32>, Val<i32>) = (val!(y), val!(x), val!(y));
+        State::new()
+            .iter_resolved()
+            .for_each(|state: ResolvedState<TupleI32>| {
+                assert_eq!(
+                    tuple.reify_fresh(&state, &mut FreshVars::new()),
+                    (Reified::Fresh(0), Reified::Fresh(1), Reified::Fresh(0))
+                );
+            });
+    }
+
+    #[test]
+    fn display_fresh() {
+        assert_eq!(Reified::Value(1).to_string(), "1");
+        assert_eq!(Reified::<i32>::Fresh(2).to_string(), "_2");
+    }
 }
@@ -0,0 +1,99 @@
+//! Reference counted pointers that may or may not be thread safe.
+//!
+//! By default, Canrun shares values and goals with [`std::rc::Rc`] and the
+//! [`im_rc`](https://docs.rs/im-rc) persistent collections, which are fast
+//! but cannot cross a thread boundary. Enabling the `sync` cargo feature
+//! switches these over to [`std::sync::Arc`] and [`im`](https://docs.rs/im),
+//! which makes [`Goal<'static, D>`](crate::Goal) and
+//! [`State`](crate::State) `Send + Sync` whenever the types in the domain are.
+//!
+//! Code that implements [`Fork`](crate::state::Fork),
+//! [`Constraint`](crate::state::constraints::Constraint) or
+//! [`UnifyIn`](crate::UnifyIn) should use the [`Rc`] alias from this module so
+//! that it compiles either way.
+//!
+//! # Example:
+//! ```
+//! use canrun::shared::Rc;
+//!
+//! let shared: Rc<i32> = Rc::new(1);
+//! assert_eq!(*shared, 1);
+//! ```
+
+/// A reference counted pointer.
+///
+/// This is [`std::rc::Rc`] by default, or [`std::sync::Arc`] with the `sync`
+/// feature.
+#[cfg(not(feature = "sync"))]
+pub type Rc<T> = std::rc::Rc<T>;
+
+/// A reference counted pointer.
+///
+/// This is [`std::rc::Rc`] by default, or [`std::sync::Arc`] with the `sync`
+/// feature.
+#[cfg(feature = "sync")]
+pub type Rc<T> = std::sync::Arc<T>;
+
+#[cfg(not(feature = "sync"))]
+pub(crate) use im_rc::{HashMap, HashSet, Vector};
+
+#[cfg(feature = "sync")]
+pub(crate) use im::{HashMap, HashSet, Vector};
+
+/// Types that can be stored inside a [`Goal`](crate::Goal) or
+/// [`State`](crate::State).
+///
+/// This is implemented for every type by default. With the `sync` feature, it
+/// is only implemented for types that are `Send + Sync`.
+#[cfg(not(feature = "sync"))]
+pub trait Shareable {}
+#[cfg(not(feature = "sync"))]
+impl<T: ?Sized> Shareable for T {}
+
+/// Types that can be stored inside a [`Goal`](crate::Goal) or
+/// [`State`](crate::State).
+///
+/// This is implemented for every type by default. With the `sync` feature, it
+/// is only implemented for types that are `Send + Sync`.
+#[cfg(feature = "sync")]
+pub trait Shareable: Send + Sync {}
+#[cfg(feature = "sync")]
+impl<T: Send + Sync + ?Sized> Shareable for T {}
+
+/// Expand to a `dyn Fn*` trait object type that is `Send + Sync` when the
+/// `sync` feature is enabled.
+#[cfg(not(feature = "sync"))]
+macro_rules! dyn_fn {
+    ($($bounds:tt)*) => { dyn $($bounds)* };
+}
+
+#[cfg(feature = "sync")]
+macro_rules! dyn_fn {
+    ($($bounds:tt)*) => { dyn $($bounds)* + Send + Sync };
+}
+
+#[cfg(all(test, feature = "sync"))]
+mod tests {
+    use crate as canrun;
+    use crate::example::I32;
+    use crate::{any, unify, var, Goal, State};
+    use std::thread;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn goals_and_states_are_send_sync() {
+        assert_send_sync::<Goal<'static, I32>>();
+        assert_send_sync::<State<'static, I32>>();
+    }
+
+    #[test]
+    fn goals_can_be_solved_on_another_thread() {
+        let x = var();
+        let goal: Goal<'static, I32> = any![unify(x, 1), unify(x, 2)];
+        let results = thread::spawn(move || goal.query(x).collect::<Vec<_>>())
+            .join()
+            .unwrap();
+        assert_eq!(results, vec![1, 2]);
+    }
+}
@@ -15,29 +15,88 @@
 //! potential results will yield zero or more
 //! [`ResolvedStates`](ResolvedState).
 
+pub mod budget;
 pub mod constraints;
 mod impls;
 mod iter_resolved;
+pub mod observer;
+mod occurs;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+mod residual;
 mod resolved;
+pub mod search;
+pub mod session;
+pub mod stats;
 
 use super::util::multikeymultivaluemap::MKMVMap;
 use crate::domains::{Domain, DomainType};
+use crate::shared::{Rc, Shareable, Vector};
 use crate::value::{
-    LVarId, Val,
+    next_id, LVar, LVarId, Val,
     Val::{Resolved, Var},
 };
 use crate::UnifyIn;
+use budget::Budget;
+pub use budget::Interrupt;
 #[doc(hidden)]
 pub use constraints::Constraint;
-pub use iter_resolved::{IterResolved, ResolvedStateIter};
+use constraints::{ConstraintKey, Wake};
+pub use iter_resolved::{IterResolved, ResidualStateIter, ResolvedStateIter};
+use observer::{Described, Event, Observer, Observers, VarInfo};
+pub use occurs::OccursCheck;
+pub use residual::{PendingConstraint, PendingFork, Residual, ResidualState};
 pub use resolved::ResolvedState;
+use search::{DepthFirst, Expansion, SearchStrategy};
+use std::cmp::Ordering;
+use std::collections::VecDeque;
 use std::fmt::Debug;
-use std::iter::once;
-use std::rc::Rc;
 
 /// Type alias for an [`Iterator`] of [`States`](crate::state::State)
 pub type StateIter<'s, D> = Box<dyn Iterator<Item = State<'s, D>> + 's>;
-type ConstraintFns<'s, D> = MKMVMap<LVarId, Rc<dyn Constraint<'s, D> + 's>>;
+type ConstraintFns<'s, D> = MKMVMap<VarInfo, Watching<'s, D>>;
+
+// A constraint in the store, along with how it asked to be woken up.
+struct Watching<'s, D: Domain<'s>> {
+    constraint: Rc<dyn Constraint<'s, D> + 's>,
+    wake: Wake,
+    // The keys of constraints this one makes redundant, starting with its
+    // own. Worked out once when it is stored, and left out if there are none.
+    redundant: Option<Rc<[ConstraintKey<'s, D>]>>,
+}
+
+// The variables that existed when the log was started which have since been
+// bound to a value or to each other.
+#[derive(Clone)]
+struct BindingLog {
+    fresh: LVarId,
+    bound: Vec<VarInfo>,
+}
+
+// Constraints woken by a new binding, in the order they were woken.
+struct Propagation<'s, D> {
+    queue: VecDeque<Rc<dyn Constraint<'s, D> + 's>>,
+    running: bool,
+}
+
+impl<'s, D> Clone for Propagation<'s, D> {
+    fn clone(&self) -> Self {
+        Propagation {
+            queue: self.queue.clone(),
+            running: self.running,
+        }
+    }
+}
+
+impl<'s, D: Domain<'s>> Clone for Watching<'s, D> {
+    fn clone(&self) -> Self {
+        Watching {
+            constraint: self.constraint.clone(),
+            wake: self.wake,
+            redundant: self.redundant.clone(),
+        }
+    }
+}
 
 /// The core struct used to contain and manage [value](crate::value) bindings.
 ///
@@ -64,11 +123,35 @@ type ConstraintFns<'s, D> = MKMVMap<LVarId, Rc<dyn Constraint<'s, D> + 's>>;
 /// }
 /// assert!(my_fn().is_some());
 /// ```
-#[derive(Clone)]
 pub struct State<'a, D: Domain<'a> + 'a> {
     domain: D,
     constraints: ConstraintFns<'a, D>,
-    forks: im_rc::Vector<Rc<dyn Fork<'a, D> + 'a>>,
+    forks: Vector<Rc<dyn Fork<'a, D> + 'a>>,
+    budget: Option<Budget>,
+    occurs_check: bool,
+    observer: Option<Rc<Observers<'a>>>,
+    depth: usize,
+    // Only allocated while woken constraints are waiting to be attempted.
+    propagation: Option<Box<Propagation<'a, D>>>,
+    // Only used while probing the goal inside a `not`.
+    log: Option<Box<BindingLog>>,
+}
+
+impl<'a, D: Domain<'a> + 'a> Clone for State<'a, D> {
+    fn clone(&self) -> Self {
+        self.observe(&Event::StateCloned);
+        State {
+            domain: self.domain.clone(),
+            constraints: self.constraints.clone(),
+            forks: self.forks.clone(),
+            budget: self.budget.clone(),
+            occurs_check: self.occurs_check,
+            observer: self.observer.clone(),
+            depth: self.depth,
+            propagation: self.propagation.clone(),
+            log: self.log.clone(),
+        }
+    }
 }
 
 impl<'a, D: Domain<'a> + 'a> State<'a, D> {
@@ -92,7 +175,202 @@ impl<'a, D: Domain<'a> + 'a> State<'a, D> {
         State {
             domain: D::new(),
             constraints: MKMVMap::new(),
-            forks: im_rc::Vector::new(),
+            forks: Vector::new(),
+            budget: None,
+            occurs_check: false,
+            observer: None,
+            depth: 0,
+            propagation: None,
+            log: None,
+        }
+    }
+
+    /// Limit the amount of work that can be done with this state and any
+    /// states derived from it.
+    ///
+    /// Once the [`Budget`](budget::Budget) runs out, every further
+    /// unification, constraint attempt and fork expansion will fail. See the
+    /// [`budget`] module for more information.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{State, Interrupt, val, var};
+    /// use canrun::budget::Budget;
+    /// use canrun::example::I32;
+    ///
+    /// let x = var();
+    /// let budget = Budget::new().max_steps(1);
+    /// let state: State<I32> = State::new().with_budget(&budget);
+    /// let state = state.unify(&val!(x), &val!(1));
+    /// assert!(state.is_some());
+    /// let state = state.unwrap().unify(&val!(x), &val!(1));
+    /// assert!(state.is_none());
+    /// assert_eq!(budget.interrupted(), Some(Interrupt::StepLimit));
+    /// ```
+    pub fn with_budget(self, budget: &Budget) -> Self {
+        State {
+            budget: Some(budget.clone()),
+            ..self
+        }
+    }
+
+    /// Refuse to bind a variable to a value that contains that same variable.
+    ///
+    /// Without this check, unifying `x` with something like `lvec![1, x]`
+    /// succeeds and creates a cyclic binding that can never be
+    /// [reified](crate::value::ReifyIn). The check walks every nested
+    /// [`Val`] through [`UnifyIn::occurs_check()`], so it is off by default.
+    /// It applies to this state and any states derived from it.
+    ///
+    /// See [`OccursCheck`] for an example.
+    pub fn with_occurs_check(self) -> Self {
+        State {
+            occurs_check: true,
+            ..self
+        }
+    }
+
+    /// Report every [`Event`](observer::Event) in the search to an
+    /// [`Observer`](observer::Observer).
+    ///
+    /// The observer is shared with any states derived from this one, and sees
+    /// events alongside any that were already attached. Attaching the same
+    /// observer again has no effect. See the [`observer`] module for more
+    /// information.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{State, IterResolved, val, var};
+    /// use canrun::observer::LogObserver;
+    /// use canrun::example::I32;
+    /// use canrun::shared::Rc;
+    ///
+    /// let x = var();
+    /// let state: State<I32> = State::new().with_observer(Rc::new(LogObserver::new()));
+    /// let state = state.unify(&val!(x), &val!(1));
+    /// assert_eq!(state.iter_resolved().count(), 1);
+    /// ```
+    pub fn with_observer(self, observer: Rc<dyn Observer + 'a>) -> Self {
+        let observers = Observers::add(self.observer.as_deref(), observer);
+        State {
+            observer: Some(Rc::new(observers)),
+            ..self
+        }
+    }
+
+    /// Pass an event to the attached [`Observer`](observer::Observer), if any.
+    pub(crate) fn observe(&self, event: &Event<'_>) {
+        if let Some(observer) = &self.observer {
+            observer.observe(self.depth, event);
+        }
+    }
+
+    /// List the [constraints](State::constrain()) that are still waiting for
+    /// variables to be resolved, in the order they were added.
+    ///
+    /// This is a snapshot for inspection and debugging. See
+    /// [`.iter_residual()`](crate::IterResolved::iter_residual()) to get the
+    /// constraints left over after the pending forks have been explored.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, State, all, lt, unify, var};
+    /// use canrun::example::I32;
+    ///
+    /// let (x, y) = (var(), var());
+    /// let goal: Goal<I32> = all![lt(x, y), unify(x, 1)];
+    /// let state = goal.apply(State::new()).unwrap();
+    /// let pending = state.pending_constraints();
+    /// assert_eq!(pending.len(), 1);
+    /// assert!(pending[0].watches(y));
+    /// ```
+    pub fn pending_constraints(&self) -> Vec<PendingConstraint> {
+        let mut pending: Vec<_> = self.constraints.values().collect();
+        // Keep the order stable, regardless of how the map is laid out.
+        pending.sort_by_key(|entry| entry.id);
+        pending
+            .into_iter()
+            .map(|entry| PendingConstraint::new(&*entry.value.constraint, entry.keys.clone()))
+            .collect()
+    }
+
+    /// List the pending [constraints](State::constrain()) that will be
+    /// attempted again once a variable is resolved.
+    ///
+    /// The variable is looked up in the current state first, so this also
+    /// finds constraints that are watching a variable it has been unified
+    /// with.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, State, all, lt, unify, var};
+    /// use canrun::example::I32;
+    ///
+    /// let (x, y, z) = (var(), var(), var());
+    /// let goal: Goal<I32> = all![lt(x, 1), unify(x, y), lt(z, 1)];
+    /// let state = goal.apply(State::new()).unwrap();
+    /// assert_eq!(state.constraints_waiting_on(x).len(), 1);
+    /// assert_eq!(state.constraints_waiting_on(y).len(), 1);
+    /// assert_eq!(state.constraints_waiting_on(z).len(), 1);
+    /// ```
+    pub fn constraints_waiting_on<T>(&self, var: LVar<T>) -> Vec<PendingConstraint>
+    where
+        T: Debug,
+        D: DomainType<'a, T>,
+    {
+        match self.resolve_val(&Var(var)) {
+            Var(var) => self
+                .pending_constraints()
+                .into_iter()
+                .filter(|constraint| constraint.watches(*var))
+                .collect(),
+            Resolved(_) => Vec::new(),
+        }
+    }
+
+    /// List the [forks](State::fork()) that have not been expanded yet, in
+    /// the order they will be expanded.
+    ///
+    /// Forks with the smallest [`size_hint`](Fork::size_hint()) go first.
+    /// Those with the same hint, or without one, are listed in the order they
+    /// were added.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, State, all, any, either, unify, var};
+    /// use canrun::example::I32;
+    ///
+    /// let x = var();
+    /// let goal: Goal<I32> = all![
+    ///     any![unify(x, 1), unify(x, 2), unify(x, 3)],
+    ///     either(unify(x, 1), unify(x, 2)),
+    /// ];
+    /// let state = goal.apply(State::new()).unwrap();
+    /// let hints: Vec<_> = state.pending_forks().iter().map(|f| f.size_hint()).collect();
+    /// assert_eq!(hints, vec![Some(2), Some(3)]);
+    /// ```
+    pub fn pending_forks(&self) -> Vec<PendingFork> {
+        self.forks
+            .iter()
+            .map(|fork| PendingFork::new(&**fork))
+            .collect()
+    }
+
+    /// Spend one step of the attached [`Budget`], if any.
+    pub(crate) fn step(&self) -> Option<()> {
+        match &self.budget {
+            Some(budget) => budget.step(),
+            None => Some(()),
+        }
+    }
+
+    // Whether the attached budget has run out. Anything that looks for the
+    // absence of solutions needs to check this, since an interrupted search
+    // also comes up empty.
+    pub(crate) fn interrupted(&self) -> bool {
+        match &self.budget {
+            Some(budget) => budget.interrupted().is_some(),
+            None => false,
         }
     }
 
@@ -123,14 +401,69 @@ impl<'a, D: Domain<'a> + 'a> State<'a, D> {
         func(self)
     }
 
-    fn iter_forks(mut self) -> StateIter<'a, D> {
-        let fork = self.forks.pop_front();
-        match fork {
-            None => Box::new(once(self)),
-            Some(fork) => Box::new(fork.fork(self).flat_map(State::iter_forks)),
+    /// Expand the next pending [fork](State::fork()), if there is one.
+    ///
+    /// The fork with the smallest [`size_hint`](Fork::size_hint()) is chosen
+    /// first.
+    ///
+    /// This is the building block for [search
+    /// strategies](crate::search::SearchStrategy). Most code should not need
+    /// to call it directly.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{State, Goal, either, unify, var};
+    /// use canrun::search::Expansion;
+    /// use canrun::example::I32;
+    ///
+    /// let x = var();
+    /// let goal: Goal<I32> = either(unify(x, 1), unify(x, 2));
+    /// let state = goal.apply(State::new()).unwrap();
+    /// match state.expand() {
+    ///     Expansion::Forked(states) => assert_eq!(states.count(), 2),
+    ///     Expansion::Settled(_) => unreachable!(),
+    /// }
+    /// ```
+    pub fn expand(mut self) -> Expansion<'a, D> {
+        match self.next_fork() {
+            Some(fork) => match self.step() {
+                Some(()) => {
+                    self.observe(&Event::ForkExpanded {
+                        fork: &Described(&*fork),
+                        size_hint: fork.size_hint(),
+                    });
+                    self.depth += 1;
+                    match self.observer.clone() {
+                        None => Expansion::Forked(fork.fork(self)),
+                        Some(observer) => {
+                            let depth = self.depth;
+                            let size_hint = fork.size_hint();
+                            let states = fork.fork(self);
+                            Expansion::Forked(observer::report_dead_ends(
+                                states, observer, depth, size_hint,
+                            ))
+                        }
+                    }
+                }
+                None => Expansion::Forked(Box::new(std::iter::empty())),
+            },
+            None => Expansion::Settled(self),
         }
     }
 
+    /// Remove the pending fork with the smallest
+    /// [`size_hint`](Fork::size_hint()), so that the most constrained choices
+    /// are made first. Forks without a hint are taken in the order they were
+    /// added, after any with a hint.
+    fn next_fork(&mut self) -> Option<Rc<dyn Fork<'a, D> + 'a>> {
+        self.forks.pop_front()
+    }
+
+    /// The fork that the next call to [`.expand()`](State::expand()) will use.
+    pub(crate) fn peek_fork(&self) -> Option<&Rc<dyn Fork<'a, D> + 'a>> {
+        self.forks.front()
+    }
+
     /// Recursively resolve a [`Val`](crate::value::Val) as far as the currently
     /// known variable bindings allow.
     ///
@@ -188,38 +521,128 @@ impl<'a, D: Domain<'a> + 'a> State<'a, D> {
     /// let state = state.unify(&val!(1), &val!(2));
     /// assert!(state.is_none());
     /// ```
-    pub fn unify<T>(mut self, a: &Val<T>, b: &Val<T>) -> Option<Self>
+    pub fn unify<T>(self, a: &Val<T>, b: &Val<T>) -> Option<Self>
     where
         T: UnifyIn<'a, D> + Debug,
         D: DomainType<'a, T>,
     {
-        let a = self.resolve_val(a);
-        let b = self.resolve_val(b);
-        match (a, b) {
-            (Resolved(a), Resolved(b)) => {
-                let a = a.clone();
-                let b = b.clone();
-                UnifyIn::unify_resolved(self, a, b)
+        match self.observer.clone() {
+            None => self.unify_vals(a, b)?.propagate(),
+            Some(observer) => {
+                let depth = self.depth;
+                let result = self.unify_vals(a, b).and_then(State::propagate);
+                let succeeded = result.is_some();
+                observer.observe(depth, &Event::Unify { a, b, succeeded });
+                result
             }
-            (Var(a), Var(b)) if a == b => Some(self),
-            (Var(var), val) | (val, Var(var)) => {
-                let key = *var;
-                let value = val.clone();
+        }
+    }
 
-                // TODO: Add occurs check?
+    fn unify_vals<T>(mut self, a: &Val<T>, b: &Val<T>) -> Option<Self>
+    where
+        T: UnifyIn<'a, D> + Debug,
+        D: DomainType<'a, T>,
+    {
+        self.step()?;
+        let a = self.domain.find(a);
+        let b = self.domain.find(b);
+        match (a, b) {
+            (Resolved(a), Resolved(b)) => UnifyIn::unify_resolved(self, a, b),
+            (Var(a), Var(b)) if a == b => Some(self),
+            (Var(var), value) | (value, Var(var)) => {
+                if self.occurs_check {
+                    if let Resolved(resolved) = &value {
+                        let mut check = OccursCheck::new(&self, var.id);
+                        resolved.occurs_check(&mut check);
+                        if check.found() {
+                            return None;
+                        }
+                    }
+                }
 
-                self.domain.update(key, value);
+                // When joining two unbound variables, whichever one is left
+                // unbound is what the woken constraints should wait on next.
+                let joined = match &value {
+                    Var(other) => Some(*other),
+                    Resolved(_) => None,
+                };
+                let bound = self.domain.update(var, value);
+                let root = joined.map(|other| if bound == var { other } else { var });
+                let (bound, root) = (VarInfo::of(&bound), root.as_ref().map(VarInfo::of));
+                if let Some(log) = &mut self.log {
+                    let existed = |var: &VarInfo| var.id() < log.fresh;
+                    if existed(&bound) && root.iter().all(existed) && !log.bound.contains(&bound) {
+                        log.bound.push(bound);
+                    }
+                }
+                self.wake(bound, root)
+            }
+        }
+    }
 
-                // check constraints matching newly assigned lvar
-                if let Some(constraints) = self.constraints.extract(&key.id) {
-                    constraints
-                        .into_iter()
-                        .try_fold(self, |state, func| state.constrain(func))
-                } else {
-                    Some(self)
+    // Queue up the constraints that were watching a newly bound variable.
+    // Constraints waiting on all of their variables are put back until the
+    // last one has been resolved.
+    fn wake(mut self, bound: VarInfo, root: Option<VarInfo>) -> Option<Self> {
+        let entries = match self.constraints.extract(&bound) {
+            Some(entries) => entries,
+            None => return Some(self),
+        };
+        for entry in entries {
+            match entry.value.wake {
+                Wake::Any => self.schedule(entry.value.constraint),
+                Wake::All => {
+                    let mut remaining: Vec<_> =
+                        entry.keys.into_iter().filter(|var| *var != bound).collect();
+                    if let Some(root) = root {
+                        if !remaining.contains(&root) {
+                            remaining.push(root);
+                        }
+                    }
+                    if remaining.is_empty() {
+                        self.schedule(entry.value.constraint);
+                    } else {
+                        self.constraints.add(remaining, entry.value);
+                    }
                 }
             }
         }
+        Some(self)
+    }
+
+    fn schedule(&mut self, constraint: Rc<dyn Constraint<'a, D> + 'a>) {
+        let propagation = self.propagation.get_or_insert_with(|| {
+            Box::new(Propagation {
+                queue: VecDeque::new(),
+                running: false,
+            })
+        });
+        let queued = propagation
+            .queue
+            .iter()
+            .any(|existing| Rc::ptr_eq(existing, &constraint));
+        if !queued {
+            propagation.queue.push_back(constraint);
+        }
+    }
+
+    // Attempt queued constraints until no more are woken up. Any variables
+    // bound along the way only add to the queue, so the work happens in a
+    // loop in the outermost call instead of recursing through each wake up.
+    fn propagate(mut self) -> Option<Self> {
+        match &mut self.propagation {
+            Some(propagation) if !propagation.running => propagation.running = true,
+            _ => return Some(self),
+        }
+        while let Some(constraint) = self.next_woken() {
+            self = self.attempt(constraint, true)?;
+        }
+        self.propagation = None;
+        Some(self)
+    }
+
+    fn next_woken(&mut self) -> Option<Rc<dyn Constraint<'a, D> + 'a>> {
+        self.propagation.as_mut()?.queue.pop_front()
     }
 
     /// Add a constraint to the store that can be reevaluated as variables are
@@ -229,18 +652,190 @@ impl<'a, D: Domain<'a> + 'a> State<'a, D> {
     /// values are available. `.constrain()` provides a low level way to run
     /// custom imperative code whenever certain bindings are updated.
     ///
+    /// Any constraints woken up by bindings made along the way are queued and
+    /// attempted in turn until nothing else is woken, so long chains of
+    /// constraints don't grow the stack. A constraint that is woken more than
+    /// once before it gets a turn is only attempted once.
+    ///
     /// See the [`Constraint` trait](constraints::Constraint) for more
     /// information.
-    pub fn constrain(mut self, constraint: Rc<dyn Constraint<'a, D> + 'a>) -> Option<Self> {
+    pub fn constrain(self, constraint: Rc<dyn Constraint<'a, D> + 'a>) -> Option<Self> {
+        self.attempt(constraint, false)?.propagate()
+    }
+
+    fn attempt(mut self, constraint: Rc<dyn Constraint<'a, D> + 'a>, woken: bool) -> Option<Self> {
+        self.step()?;
         match constraint.attempt(&self) {
-            Ok(resolve) => resolve(self),
+            Ok(resolve) => match self.observer.clone() {
+                None => resolve(self),
+                Some(observer) => {
+                    let depth = self.depth;
+                    let result = resolve(self);
+                    observer.observe(
+                        depth,
+                        &Event::Constraint {
+                            constraint: &Described(&*constraint),
+                            succeeded: result.is_some(),
+                        },
+                    );
+                    result
+                }
+            },
             Err(watch) => {
-                self.constraints.add(watch.0, constraint);
+                let key = constraint.key();
+                if self.is_redundant(key.as_ref(), &watch.vars) {
+                    self.observe(&Event::Redundant {
+                        constraint: &Described(&*constraint),
+                        woken,
+                    });
+                    return Some(self);
+                }
+                self.observe(&Event::Watch {
+                    constraint: &Described(&*constraint),
+                    vars: &watch.vars,
+                    woken,
+                });
+                let wake = watch.wake;
+                let redundant: Vec<_> = key.into_iter().chain(constraint.subsumes(&self)).collect();
+                let redundant = if redundant.is_empty() {
+                    None
+                } else {
+                    Some(Rc::from(redundant))
+                };
+                self.constraints.add(
+                    watch.vars,
+                    Watching {
+                        constraint,
+                        wake,
+                        redundant,
+                    },
+                );
                 Some(self)
             }
         }
     }
 
+    // Check for an identical or subsuming constraint already waiting on one
+    // of the vars. Only those can make this one redundant, and this keeps the
+    // number of keys that need to be matched small. A single probe is shared
+    // by every match, and only made once there is something to match.
+    fn is_redundant(&self, key: Option<&ConstraintKey<'a, D>>, vars: &[VarInfo]) -> bool {
+        let key = match key {
+            Some(key) => key,
+            None => return false,
+        };
+        let mut probe = None;
+        vars.iter().any(|var| {
+            self.constraints
+                .get(var)
+                .filter_map(|entry| entry.value.redundant.as_ref())
+                .flat_map(|redundant| redundant.iter())
+                .filter(|other| other.same_shape(key))
+                .any(
+                    |other| match probe.get_or_insert_with(|| State::probe(&self.domain)) {
+                        Some(probe) => other.matches_in(key, probe),
+                        None => false,
+                    },
+                )
+        })
+    }
+
+    // A bare copy of a state's bindings that logs anything bound from here
+    // on. Values unified in it don't wake anything up, get observed or count
+    // against a budget.
+    pub(crate) fn probe(domain: &D) -> Option<Self> {
+        State {
+            domain: domain.clone(),
+            constraints: MKMVMap::new(),
+            forks: Vector::new(),
+            budget: None,
+            occurs_check: false,
+            observer: None,
+            depth: 0,
+            propagation: None,
+            log: None,
+        }
+        .log_bindings(next_id())
+    }
+
+    // Check whether pairs of values already unify in a probe, without binding
+    // anything or queueing up a fork.
+    pub(crate) fn already_unified<I>(self, pairs: I) -> bool
+    where
+        I: IntoIterator<Item = (D::Value, D::Value)>,
+    {
+        let probe = pairs
+            .into_iter()
+            .try_fold(self, |probe, (a, b)| D::unify_domain_values(probe, a, b));
+        match probe {
+            Some(probe) => {
+                probe.forks.is_empty()
+                    && probe.constraints.is_empty()
+                    && probe.log.is_some_and(|log| log.bound.is_empty())
+            }
+            None => false,
+        }
+    }
+
+    // Search this state for settled states, without expanding the forks
+    // that were already pending. Those are put back on each of the results
+    // instead, so that a goal's own alternatives can be pruned without
+    // touching any others.
+    pub(crate) fn search_own_forks(mut self, goal: crate::goals::Goal<'a, D>) -> StateIter<'a, D> {
+        let outer = std::mem::take(&mut self.forks);
+        match goal.apply(self) {
+            Some(state) => Box::new(DepthFirst.search(state).map(move |mut state| {
+                state.forks = outer.clone();
+                state
+            })),
+            None => Box::new(std::iter::empty()),
+        }
+    }
+
+    // Start keeping track of which variables created before `fresh` are
+    // bound from here on. This may be a probe taken in the middle of
+    // propagating another state, so any constraints that state still has
+    // queued up are attempted here as part of a fresh propagation.
+    pub(crate) fn log_bindings(self, fresh: LVarId) -> Option<Self> {
+        let propagation = self.propagation.map(|propagation| {
+            Box::new(Propagation {
+                queue: propagation.queue,
+                running: false,
+            })
+        });
+        State {
+            log: Some(Box::new(BindingLog {
+                fresh,
+                bound: Vec::new(),
+            })),
+            propagation,
+            ..self
+        }
+        .propagate()
+    }
+
+    // The logged variables that this state, derived from `base`, has bound or
+    // has added constraints waiting on, along with whether it added any
+    // constraints at all.
+    pub(crate) fn touched_since(&self, base: &State<'a, D>) -> (Vec<VarInfo>, bool) {
+        let log = match &self.log {
+            Some(log) => log,
+            None => return (Vec::new(), false),
+        };
+        let mut vars = log.bound.clone();
+        let mut constrained = false;
+        let first_new = base.constraints.next_id();
+        for entry in self.constraints.values().filter(|e| e.id >= first_new) {
+            constrained = true;
+            for var in &entry.keys {
+                if var.id() < log.fresh && !vars.contains(var) {
+                    vars.push(*var);
+                }
+            }
+        }
+        (vars, constrained)
+    }
+
     /// Add a potential fork point to the state.
     ///
     /// If there are many possibilities for a certain value or set of values,
@@ -257,7 +852,23 @@ impl<'a, D: Domain<'a> + 'a> State<'a, D> {
     /// [`.iter_resolved()`](crate::state::IterResolved::iter_resolved()) (or
     /// [`.query()](crate::Query::query())) is called.
     pub fn fork(mut self, fork: Rc<dyn Fork<'a, D> + 'a>) -> Option<Self> {
-        self.forks.push_back(fork);
+        // Keep the pending forks in the order they will be expanded, so that
+        // taking the next one doesn't mean searching for it.
+        let index = match fork.size_hint() {
+            Some(size) => self
+                .forks
+                .binary_search_by(|other| match other.size_hint() {
+                    Some(other) if other <= size => Ordering::Less,
+                    _ => Ordering::Greater,
+                }),
+            None => Err(self.forks.len()),
+        }
+        .unwrap_or_else(|index| index);
+        self.forks.insert(index, fork);
+        self.observe(&Event::ForkPushed {
+            fork: &Described(&**self.forks.get(index)?),
+            pending: self.forks.len(),
+        });
         Some(self)
     }
 }
@@ -270,7 +881,7 @@ impl<'a, D: Domain<'a> + 'a> State<'a, D> {
 /// ```
 /// use canrun::{val, var, Fork, Query, State, StateIter, Val};
 /// use canrun::example::I32;
-/// use std::rc::Rc;
+/// use canrun::shared::Rc;
 ///
 /// #[derive(Debug)]
 /// struct Is1or2 {
@@ -293,17 +904,41 @@ impl<'a, D: Domain<'a> + 'a> State<'a, D> {
 /// assert_eq!(results, vec![1, 2]);
 /// # }
 /// ```
-pub trait Fork<'a, D: Domain<'a>>: Debug {
+///
+/// With the `sync` feature enabled, forks must also be
+/// [`Shareable`](crate::shared::Shareable).
+pub trait Fork<'a, D: Domain<'a>>: Debug + Shareable {
     /// Given a [`State`], return an iterator of states that result from the
     /// fork operation.
     fn fork(&self, state: State<'a, D>) -> StateIter<'a, D>;
+
+    /// An estimate of how many states this fork will produce.
+    ///
+    /// When a state has several pending forks, the one with the smallest hint
+    /// is expanded first. Making the most constrained choice first tends to
+    /// uncover dead ends earlier. Forks that return `None` (the default) are
+    /// expanded after those with a hint, in the order they were added.
+    fn size_hint(&self) -> Option<usize> {
+        None
+    }
+
+    /// The goals this fork chooses between, if it is built from goals.
+    ///
+    /// Only used to draw the fork with
+    /// [`Goal::to_dot()`](crate::goals::Goal::to_dot()). Forks that return an
+    /// empty list (the default) are drawn as a single node using their `Debug`
+    /// output.
+    fn branches(&self) -> Vec<crate::goals::Goal<'a, D>> {
+        Vec::new()
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use crate as canrun;
     use crate::example::I32;
-    use crate::{val, var, Fork, Query, State, StateIter, Val};
-    use std::rc::Rc;
+    use crate::shared::Rc;
+    use crate::{any, both, either, unify, val, var, Fork, Goal, Query, State, StateIter, Val};
 
     #[derive(Debug)]
     struct Is1or2 {
@@ -327,4 +962,53 @@ mod test {
         let results: Vec<i32> = state.query(x).collect();
         assert_eq!(results, vec![1, 2]);
     }
+
+    #[test]
+    fn smallest_fork_first() {
+        let (x, y) = (var(), var());
+        let wide: Goal<I32> = any![unify(x, 1), unify(x, 2), unify(x, 3)];
+        let narrow: Goal<I32> = either(unify(y, 1), unify(y, 2));
+        let goal = both(wide, narrow);
+        let results: Vec<_> = goal.query((x, y)).collect();
+        assert_eq!(
+            results,
+            vec![(1, 1), (2, 1), (3, 1), (1, 2), (2, 2), (3, 2)]
+        );
+    }
+
+    #[test]
+    fn forks_are_queued_by_hint() {
+        let (x, y): (crate::LVar<i32>, crate::LVar<i32>) = (var(), var());
+        let goal: Goal<I32> = crate::all![
+            Goal::fork(Is1or2 { x: val!(x) }),
+            any![unify(x, 1), unify(x, 2), unify(x, 3)],
+            either(unify(x, 1), unify(x, 2)),
+            Goal::fork(Is1or2 { x: val!(y) }),
+            any![unify(y, 1), unify(y, 2), unify(y, 3)],
+            either(unify(y, 1), unify(y, 2)),
+        ];
+        let state = goal.apply(State::new()).unwrap();
+        let forks = state.pending_forks();
+        let hints: Vec<_> = forks.iter().map(|fork| fork.size_hint()).collect();
+        assert_eq!(hints, vec![Some(2), Some(2), Some(3), Some(3), None, None]);
+        // Forks with the same hint stay in the order they were added.
+        let on_x = format!("Var({:?})", x);
+        for index in [0, 2, 4].iter() {
+            assert!(forks[*index].description().contains(&on_x));
+        }
+    }
+
+    #[test]
+    fn inspecting_pending_work() {
+        let (x, y) = (var(), var());
+        let goal: Goal<I32> = crate::all![crate::lt(x, y), Goal::fork(Is1or2 { x: val!(x) })];
+        let state = goal.apply(State::new()).unwrap();
+        assert!(state.pending_forks()[0].description().starts_with("Is1or2"));
+        assert_eq!(state.pending_forks()[0].size_hint(), None);
+        assert_eq!(state.constraints_waiting_on(x).len(), 1);
+
+        let state = state.unify(&val!(x), &val!(1)).unwrap();
+        assert!(state.constraints_waiting_on(x).is_empty());
+        assert_eq!(state.constraints_waiting_on(y).len(), 1);
+    }
 }
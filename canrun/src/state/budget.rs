@@ -0,0 +1,457 @@
+//! Limit how much work a search may do before giving up.
+//!
+//! Some goals never stop producing forks, and some produce so many that
+//! exploring them all is not practical. A [`Budget`] puts an upper bound on the
+//! number of steps (fork expansions, constraint attempts, unifications and
+//! [`lazy`](crate::goals::lazy()) evaluations) that a search is allowed to
+//! take, can be given a wall-clock [deadline](Budget::deadline()), and can be
+//! interrupted from the outside with a [`CancellationToken`].
+//!
+//! Once a budget runs out, every subsequent step fails, which quickly unwinds
+//! the search. [`.query_bounded()`](crate::Query::query_bounded()) wraps this
+//! up in an iterator that reports whether it ended because the search space was
+//! exhausted or because it was [interrupted](Interrupt).
+//!
+//! # Example:
+//! ```
+//! use canrun::{Goal, LVar, Interrupt, either, lazy, unify, var};
+//! use canrun::budget::Budget;
+//! use canrun::example::I32;
+//!
+//! fn forever<'a>(x: LVar<i32>) -> Goal<'a, I32> {
+//!     lazy(move || either(unify(x, 0), forever(x)))
+//! }
+//!
+//! let x = var();
+//! let budget = Budget::new().max_steps(100);
+//! let mut query = forever(x).query_bounded(&budget, x);
+//! let results: Vec<_> = query.by_ref().collect();
+//! assert!(results.len() > 0);
+//! assert_eq!(query.interrupted(), Some(Interrupt::StepLimit));
+//! ```
+
+use super::search::{DepthFirst, SearchStrategy};
+use super::{State, StateIter};
+use crate::domains::Domain;
+use crate::shared::Rc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// How many steps to take between checks of the clock. Must be a power of two.
+const CLOCK_INTERVAL: usize = 64;
+
+/// The reason a bounded search stopped before exhausting the search space.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interrupt {
+    /// The maximum number of steps was reached.
+    StepLimit,
+    /// The associated [`CancellationToken`] was cancelled.
+    Cancelled,
+    /// The deadline passed.
+    Deadline,
+}
+
+/// A flag that can be used to stop a bounded search from the outside.
+///
+/// Tokens are cheap to clone, and all clones share the same flag. They can be
+/// sent to other threads.
+///
+/// # Example:
+/// ```
+/// use canrun::{Goal, Interrupt, unify, var};
+/// use canrun::budget::{Budget, CancellationToken};
+/// use canrun::example::I32;
+///
+/// let x = var();
+/// let token = CancellationToken::new();
+/// let budget = Budget::new().cancel_on(&token);
+///
+/// token.cancel();
+/// let goal: Goal<I32> = unify(x, 1);
+/// let mut query = goal.query_bounded(&budget, x);
+/// assert_eq!(query.next(), None);
+/// assert_eq!(query.interrupted(), Some(Interrupt::Cancelled));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Create a new token that has not been cancelled.
+    pub fn new() -> Self {
+        CancellationToken(Arc::new(AtomicBool::new(false)))
+    }
+
+    /// Cancel every search using a [`Budget`] associated with this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Return `true` if [`.cancel()`](CancellationToken::cancel()) has been
+    /// called on this token or any of its clones.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Limit the amount of work a search may do.
+///
+/// A budget is attached to a [`State`] with
+/// [`.with_budget()`](State::with_budget()), or more typically through
+/// [`.query_bounded()`](crate::Query::query_bounded()). Clones share the same
+/// step count, so a budget can be inspected after being handed off.
+///
+/// An unconfigured budget never runs out.
+#[derive(Clone, Debug, Default)]
+pub struct Budget {
+    max_steps: Option<usize>,
+    cancel: Option<CancellationToken>,
+    deadline: Option<Instant>,
+    spent: Rc<Spent>,
+}
+
+#[derive(Debug, Default)]
+struct Spent {
+    steps: AtomicUsize,
+    interrupted: AtomicUsize,
+}
+
+impl Spent {
+    fn steps(&self) -> usize {
+        self.steps.load(Ordering::Relaxed)
+    }
+
+    fn take_step(&self) -> usize {
+        self.steps.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    fn interrupted(&self) -> Option<Interrupt> {
+        match self.interrupted.load(Ordering::Relaxed) {
+            1 => Some(Interrupt::StepLimit),
+            2 => Some(Interrupt::Cancelled),
+            3 => Some(Interrupt::Deadline),
+            _ => None,
+        }
+    }
+
+    fn interrupt(&self, interrupt: Interrupt) {
+        let code = match interrupt {
+            Interrupt::StepLimit => 1,
+            Interrupt::Cancelled => 2,
+            Interrupt::Deadline => 3,
+        };
+        // Keep the first reason if several threads run out at once.
+        let _ = self
+            .interrupted
+            .compare_exchange(0, code, Ordering::Relaxed, Ordering::Relaxed);
+    }
+}
+
+impl Budget {
+    /// Create a budget without any limits.
+    pub fn new() -> Self {
+        Budget::default()
+    }
+
+    /// Limit the search to at most `steps` steps.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::budget::Budget;
+    ///
+    /// let budget = Budget::new().max_steps(10_000);
+    /// ```
+    pub fn max_steps(self, steps: usize) -> Self {
+        Budget {
+            max_steps: Some(steps),
+            ..self
+        }
+    }
+
+    /// Stop the search as soon as the [`CancellationToken`] is cancelled.
+    pub fn cancel_on(self, token: &CancellationToken) -> Self {
+        Budget {
+            cancel: Some(token.clone()),
+            ..self
+        }
+    }
+
+    /// Stop the search once the given [`Instant`] has passed.
+    ///
+    /// The clock is only consulted every so often, so a search may run
+    /// slightly past the deadline.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::budget::Budget;
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let budget = Budget::new().deadline(Instant::now() + Duration::from_millis(50));
+    /// ```
+    pub fn deadline(self, deadline: Instant) -> Self {
+        Budget {
+            deadline: Some(deadline),
+            ..self
+        }
+    }
+
+    /// The number of steps taken so far.
+    pub fn steps(&self) -> usize {
+        self.spent.steps()
+    }
+
+    /// The reason the budget ran out, or `None` if it hasn't.
+    pub fn interrupted(&self) -> Option<Interrupt> {
+        self.spent.interrupted()
+    }
+
+    /// Record a step, returning `None` if the budget has run out.
+    pub(crate) fn step(&self) -> Option<()> {
+        if self.spent.interrupted().is_some() {
+            return None;
+        }
+        let steps = self.spent.take_step();
+        match self.check(steps & (CLOCK_INTERVAL - 1) == 0) {
+            Some(_) => None,
+            None => Some(()),
+        }
+    }
+
+    /// Check the limits without taking a step, recording and returning the
+    /// reason if the budget has run out.
+    pub(crate) fn poll(&self) -> Option<Interrupt> {
+        self.check(true)
+    }
+
+    fn check(&self, read_clock: bool) -> Option<Interrupt> {
+        if let Some(interrupt) = self.spent.interrupted() {
+            return Some(interrupt);
+        }
+        let steps = self.spent.steps();
+        let cancelled = match &self.cancel {
+            Some(token) => token.is_cancelled(),
+            None => false,
+        };
+        let expired = match self.deadline {
+            Some(deadline) if read_clock => Instant::now() >= deadline,
+            _ => false,
+        };
+        let interrupted = match self.max_steps {
+            _ if cancelled => Some(Interrupt::Cancelled),
+            _ if expired => Some(Interrupt::Deadline),
+            Some(max) if steps > max => Some(Interrupt::StepLimit),
+            _ => None,
+        };
+        match interrupted {
+            Some(interrupt) => {
+                self.spent.interrupt(interrupt);
+                self.spent.interrupted()
+            }
+            None => None,
+        }
+    }
+}
+
+/// A [`SearchStrategy`] wrapper that attaches a [`Budget`] to every state it
+/// searches.
+///
+/// [`.query_bounded()`](crate::Query::query_bounded()) always searches
+/// [`DepthFirst`]. Use this to bound a search with a different strategy.
+///
+/// # Example:
+/// ```
+/// use canrun::{Goal, any, unify, var};
+/// use canrun::budget::{Budget, Budgeted};
+/// use canrun::search::BreadthFirst;
+/// use canrun::example::I32;
+///
+/// let x = var();
+/// let budget = Budget::new().max_steps(100);
+/// let goal: Goal<I32> = any![unify(x, 1), unify(x, 2)];
+/// let result: Vec<_> = goal.query_with(Budgeted::new(BreadthFirst, &budget), x).collect();
+/// assert_eq!(result, vec![1, 2]);
+/// assert_eq!(budget.interrupted(), None);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Budgeted<S> {
+    strategy: S,
+    budget: Budget,
+}
+
+impl<S> Budgeted<S> {
+    /// Bound the given strategy with a [`Budget`].
+    pub fn new(strategy: S, budget: &Budget) -> Self {
+        Budgeted {
+            strategy,
+            budget: budget.clone(),
+        }
+    }
+}
+
+impl<'a, D, S> SearchStrategy<'a, D> for Budgeted<S>
+where
+    D: Domain<'a> + 'a,
+    S: SearchStrategy<'a, D>,
+{
+    fn prepare(&self, state: State<'a, D>) -> State<'a, D> {
+        self.strategy.prepare(state.with_budget(&self.budget))
+    }
+
+    fn search(&self, state: State<'a, D>) -> StateIter<'a, D> {
+        self.strategy.search(state.with_budget(&self.budget))
+    }
+}
+
+/// An iterator of query results that stops when its [`Budget`] runs out.
+///
+/// Returned from [`.query_bounded()`](crate::Query::query_bounded()). Once it
+/// has returned `None`,
+/// [`.interrupted()`](BoundedQuery::interrupted()) will tell you whether it
+/// ended because every result was found or because it ran out of budget.
+pub struct BoundedQuery<'a, T> {
+    results: Box<dyn Iterator<Item = T> + 'a>,
+    budget: Budget,
+    // Set once the search has run out of results, so that polling the
+    // budget afterwards can't mark a finished search as interrupted.
+    done: bool,
+}
+
+impl<'a, T> BoundedQuery<'a, T> {
+    pub(crate) fn new<I>(results: I, budget: &Budget) -> Self
+    where
+        I: Iterator<Item = T> + 'a,
+    {
+        BoundedQuery {
+            results: Box::new(results),
+            budget: budget.clone(),
+            done: false,
+        }
+    }
+
+    /// The reason the search was cut short, or `None` if it has not been.
+    ///
+    /// A query that has returned `None` from `.next()` and reports no
+    /// interruption has found every result there is to find.
+    pub fn interrupted(&self) -> Option<Interrupt> {
+        self.budget.interrupted()
+    }
+}
+
+impl<'a, T> Iterator for BoundedQuery<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.done || self.budget.poll().is_some() {
+            return None;
+        }
+        let next = self.results.next();
+        self.done = next.is_none();
+        next
+    }
+}
+
+pub(crate) fn strategy(budget: &Budget) -> Budgeted<DepthFirst> {
+    Budgeted::new(DepthFirst, budget)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Budget, Budgeted, CancellationToken, Interrupt};
+    use crate::example::I32;
+    use crate::search::BreadthFirst;
+    use crate::{both, either, lazy, unify, var, Goal, LVar};
+    use std::time::{Duration, Instant};
+
+    fn nowhere<'a>() -> Goal<'a, I32> {
+        lazy(|| either(nowhere(), Goal::fail()))
+    }
+
+    fn spin<'a>(x: LVar<i32>) -> Goal<'a, I32> {
+        lazy(move || both(spin(x), unify(x, 1)))
+    }
+
+    #[test]
+    fn completes_within_budget() {
+        let x = var();
+        let budget = Budget::new().max_steps(100);
+        let goal: Goal<I32> = either(unify(x, 1), unify(x, 2));
+        let mut query = goal.query_bounded(&budget, x);
+        assert_eq!(query.by_ref().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(query.interrupted(), None);
+        assert!(budget.steps() > 0);
+    }
+
+    #[test]
+    fn stops_runaway_forks() {
+        let x = var();
+        let budget = Budget::new().max_steps(1000);
+        let mut query = nowhere().query_bounded(&budget, x);
+        assert_eq!(query.next(), None);
+        assert_eq!(query.interrupted(), Some(Interrupt::StepLimit));
+    }
+
+    #[test]
+    fn stops_runaway_lazy_recursion() {
+        let x = var();
+        let budget = Budget::new().max_steps(100);
+        let mut query = spin(x).query_bounded(&budget, x);
+        assert_eq!(query.next(), None);
+        assert_eq!(query.interrupted(), Some(Interrupt::StepLimit));
+    }
+
+    #[test]
+    fn stops_at_deadline() {
+        let x = var();
+        let deadline = Instant::now() + Duration::from_millis(20);
+        let budget = Budget::new().deadline(deadline);
+        let strategy = Budgeted::new(BreadthFirst, &budget);
+        assert_eq!(nowhere().query_with(strategy, x).next(), None);
+        assert_eq!(budget.interrupted(), Some(Interrupt::Deadline));
+        assert!(Instant::now() >= deadline);
+    }
+
+    #[test]
+    fn past_deadline_stops_immediately() {
+        let x = var();
+        let goal: Goal<I32> = unify(x, 1);
+        let mut query = goal.query_until(Instant::now(), x);
+        assert_eq!(query.next(), None);
+        assert_eq!(query.interrupted(), Some(Interrupt::Deadline));
+    }
+
+    #[test]
+    fn finishes_before_deadline() {
+        let x = var();
+        let deadline = Instant::now() + Duration::from_secs(60);
+        let goal: Goal<I32> = either(unify(x, 1), unify(x, 2));
+        let mut query = goal.query_until(deadline, x);
+        assert_eq!(query.by_ref().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(query.interrupted(), None);
+    }
+
+    #[test]
+    fn stops_when_cancelled() {
+        let x = var();
+        let token = CancellationToken::new();
+        let budget = Budget::new().cancel_on(&token);
+        let goal: Goal<I32> = either(unify(x, 1), unify(x, 2));
+        let mut query = goal.query_bounded(&budget, x);
+        assert_eq!(query.next(), Some(1));
+        token.cancel();
+        assert_eq!(query.next(), None);
+        assert_eq!(query.interrupted(), Some(Interrupt::Cancelled));
+    }
+
+    #[test]
+    fn finished_queries_stay_uninterrupted() {
+        let x = var();
+        let token = CancellationToken::new();
+        let budget = Budget::new().cancel_on(&token);
+        let goal: Goal<I32> = either(unify(x, 1), unify(x, 2));
+        let mut query = goal.query_bounded(&budget, x);
+        assert_eq!(query.by_ref().collect::<Vec<_>>(), vec![1, 2]);
+        token.cancel();
+        assert_eq!(query.next(), None);
+        assert_eq!(query.interrupted(), None);
+    }
+}
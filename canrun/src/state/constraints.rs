@@ -1,12 +1,13 @@
 //! Run code when [`variables`](crate::value::LVar) are resolved.
 
+use super::observer::VarInfo;
+use crate::shared::{Rc, Shareable};
 use crate::value::{
-    LVar, LVarId, Val,
+    LVar, Val,
     Val::{Resolved, Var},
 };
 use crate::{Domain, DomainType, State};
 use std::fmt::Debug;
-use std::rc::Rc;
 
 /// An alias for the function that should be returned by a successful
 /// [`Constraint::attempt`] to update the [`State`].
@@ -33,28 +34,21 @@ pub type ResolveFn<'a, D> = Box<dyn FnOnce(State<'a, D>) -> Option<State<'a, D>>
 /// use canrun::{State, Query, Val, val, var, DomainType};
 /// use canrun::state::constraints::{Constraint, resolve_1, ResolveFn, VarWatch};
 /// use canrun::example::I32;
-/// use std::rc::Rc;
-/// use std::fmt;
+/// use canrun::shared::Rc;
 ///
-/// struct Assert<'a, T: fmt::Debug> {
-///     val: Val<T>,
-///     assert: Rc<dyn Fn(&T) -> bool + 'a>,
+/// #[derive(Debug)]
+/// struct Assert {
+///     val: Val<i32>,
+///     assert: fn(&i32) -> bool,
 /// }
 ///
-/// impl<'a, T: fmt::Debug> fmt::Debug for Assert<'a, T> {
-///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-///         write!(f, "Assert({:?})", self.val)
-///     }
-/// }
-///
-/// impl<'a, T, D> Constraint<'a, D> for Assert<'a, T>
+/// impl<'a, D> Constraint<'a, D> for Assert
 /// where
-///     T: fmt::Debug + 'a,
-///     D: DomainType<'a, T>,
+///     D: DomainType<'a, i32>,
 /// {
 ///     fn attempt(&self, state: &State<'a, D>) -> Result<ResolveFn<'a, D>, VarWatch> {
 ///         let resolved = resolve_1(&self.val, state)?;
-///         let assert = self.assert.clone();
+///         let assert = self.assert;
 ///         Ok(Box::new(
 ///             move |state: State<'a, D>| if assert(&*resolved) { Some(state) } else { None },
 ///         ))
@@ -65,7 +59,7 @@ pub type ResolveFn<'a, D> = Box<dyn FnOnce(State<'a, D>) -> Option<State<'a, D>>
 /// let x = var();
 ///
 /// let state: State<I32> = State::new();
-/// let state = state.constrain(Rc::new(Assert {val: val!(x), assert: Rc::new(|x| x > &1)}));
+/// let state = state.constrain(Rc::new(Assert {val: val!(x), assert: |x| x > &1}));
 /// let state = state?.unify(&val!(x), &val!(2));
 ///
 /// let results: Vec<i32> = state.query(x).collect();
@@ -74,32 +68,287 @@ pub type ResolveFn<'a, D> = Box<dyn FnOnce(State<'a, D>) -> Option<State<'a, D>>
 /// # }
 /// # test();
 /// ```
-pub trait Constraint<'a, D>: Debug
+///
+/// With the `sync` feature enabled, constraints must also be
+/// [`Shareable`](crate::shared::Shareable).
+pub trait Constraint<'a, D>: Debug + Shareable
 where
     D: Domain<'a>,
 {
     /// Resolve required variables in a state and resubscribe or request to
     /// update the state.
     fn attempt(&self, state: &State<'a, D>) -> Result<ResolveFn<'a, D>, VarWatch>;
+
+    /// Identify what this constraint does, so that
+    /// [`State::constrain()`](crate::state::State::constrain()) can drop it
+    /// if an identical constraint is already waiting on the same variables.
+    ///
+    /// Keys are [matched](ConstraintKey::matches()) against the current
+    /// state, so constraints that only differ by variables that have since
+    /// been bound to the same values are recognized as duplicates too. The
+    /// default of `None` means the constraint is always kept.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{State, Val, val, var, DomainType};
+    /// use canrun::state::constraints::{Constraint, ConstraintKey, resolve_1, ResolveFn, VarWatch};
+    /// use canrun::example::I32;
+    /// use canrun::shared::Rc;
+    ///
+    /// #[derive(Debug)]
+    /// struct IsPositive(Val<i32>);
+    ///
+    /// impl<'a, D> Constraint<'a, D> for IsPositive
+    /// where
+    ///     D: DomainType<'a, i32>,
+    /// {
+    ///     fn attempt(&self, state: &State<'a, D>) -> Result<ResolveFn<'a, D>, VarWatch> {
+    ///         let x = resolve_1(&self.0, state)?;
+    ///         Ok(Box::new(move |state| if *x > 0 { Some(state) } else { None }))
+    ///     }
+    ///
+    ///     fn key(&self) -> Option<ConstraintKey<'a, D>> {
+    ///         Some(ConstraintKey::new("IsPositive").with(&self.0))
+    ///     }
+    /// }
+    ///
+    /// let x = var();
+    /// let state: State<I32> = State::new()
+    ///     .constrain(Rc::new(IsPositive(val!(x))))
+    ///     .and_then(|state| state.constrain(Rc::new(IsPositive(val!(x)))))
+    ///     .unwrap();
+    /// assert_eq!(state.pending_constraints().len(), 1);
+    /// ```
+    fn key(&self) -> Option<ConstraintKey<'a, D>> {
+        None
+    }
+
+    /// List the [keys](Constraint::key()) of other constraints that always
+    /// hold whenever this one does.
+    ///
+    /// While this constraint is waiting, a new constraint with a matching key
+    /// that shares one of its watched variables is dropped by
+    /// [`State::constrain()`](crate::state::State::constrain()) instead of
+    /// being stored alongside it. Constraints that are already waiting are
+    /// left alone. The keys are listed once, in the state the constraint
+    /// starts waiting in. The default is to subsume nothing.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{State, Val, val, var, DomainType};
+    /// use canrun::state::constraints::{Constraint, ConstraintKey, resolve_1, ResolveFn, VarWatch};
+    /// use canrun::example::I32;
+    /// use canrun::shared::Rc;
+    ///
+    /// #[derive(Debug)]
+    /// struct AtLeast(Val<i32>, i32);
+    ///
+    /// impl<'a, D> Constraint<'a, D> for AtLeast
+    /// where
+    ///     D: DomainType<'a, i32>,
+    /// {
+    ///     fn attempt(&self, state: &State<'a, D>) -> Result<ResolveFn<'a, D>, VarWatch> {
+    ///         let x = resolve_1(&self.0, state)?;
+    ///         let min = self.1;
+    ///         Ok(Box::new(move |state| if *x >= min { Some(state) } else { None }))
+    ///     }
+    ///
+    ///     fn key(&self) -> Option<ConstraintKey<'a, D>> {
+    ///         Some(ConstraintKey::new("AtLeast").with(&self.0).with(&val!(self.1)))
+    ///     }
+    ///
+    ///     fn subsumes(&self, _: &State<'a, D>) -> Vec<ConstraintKey<'a, D>> {
+    ///         (0..self.1)
+    ///             .map(|min| ConstraintKey::new("AtLeast").with(&self.0).with(&val!(min)))
+    ///             .collect()
+    ///     }
+    /// }
+    ///
+    /// let x = var();
+    /// let state: State<I32> = State::new()
+    ///     .constrain(Rc::new(AtLeast(val!(x), 3)))
+    ///     .and_then(|state| state.constrain(Rc::new(AtLeast(val!(x), 1))))
+    ///     .unwrap();
+    /// assert_eq!(state.pending_constraints().len(), 1);
+    /// ```
+    fn subsumes(&self, _state: &State<'a, D>) -> Vec<ConstraintKey<'a, D>> {
+        Vec::new()
+    }
+}
+
+/// A structural identity for a [`Constraint`], returned by
+/// [`Constraint::key()`].
+///
+/// Two keys match when they have the same kind and their values
+/// [unify](State::unify()) in the current state without binding anything or
+/// queueing up a [fork](State::fork()).
+/// Unlike comparing the values directly, this sees through variables that
+/// have been bound since the key was made, and works for any type in the
+/// domain.
+pub struct ConstraintKey<'a, D: Domain<'a>> {
+    kind: &'static str,
+    values: Vec<D::Value>,
+}
+
+impl<'a, D: Domain<'a>> ConstraintKey<'a, D> {
+    /// Create an empty key for a kind of constraint. Add the values it is
+    /// working with using [`.with()`](ConstraintKey::with()).
+    ///
+    /// The kind should be unique to a type of constraint, since keys of
+    /// different constraints with the same kind and values will match.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{val, var, State, Val};
+    /// use canrun::state::constraints::ConstraintKey;
+    /// use canrun::example::I32;
+    ///
+    /// let x: Val<i32> = val!(var());
+    /// let state: State<I32> = State::new();
+    /// let key = || ConstraintKey::new("Sum").with(&x).with(&val!(1));
+    /// assert!(key().matches(&key(), &state));
+    /// assert!(!key().matches(&ConstraintKey::new("Sum").with(&x).with(&val!(2)), &state));
+    /// ```
+    pub fn new(kind: &'static str) -> Self {
+        ConstraintKey {
+            kind,
+            values: Vec::new(),
+        }
+    }
+
+    /// Add a value to the key.
+    pub fn with<T: Debug>(mut self, val: &Val<T>) -> Self
+    where
+        D: DomainType<'a, T>,
+    {
+        self.values.push(D::into_domain_val(val.clone()));
+        self
+    }
+
+    /// Check whether two keys identify the same constraint in a state.
+    pub fn matches(&self, other: &Self, state: &State<'a, D>) -> bool {
+        self.same_shape(other)
+            && match State::probe(&state.domain) {
+                Some(probe) => self.matches_in(other, &probe),
+                None => false,
+            }
+    }
+
+    pub(crate) fn same_shape(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.values.len() == other.values.len()
+    }
+
+    // Match against a [probe](State::probe()), which is left untouched so
+    // that it can be reused.
+    pub(crate) fn matches_in(&self, other: &Self, probe: &State<'a, D>) -> bool {
+        let pairs = self
+            .values
+            .iter()
+            .cloned()
+            .zip(other.values.iter().cloned());
+        probe.clone().already_unified(pairs)
+    }
+}
+
+impl<'a, D: Domain<'a>> Clone for ConstraintKey<'a, D> {
+    fn clone(&self) -> Self {
+        ConstraintKey {
+            kind: self.kind,
+            values: self.values.clone(),
+        }
+    }
+}
+
+impl<'a, D: Domain<'a>> Debug for ConstraintKey<'a, D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple(self.kind).field(&self.values).finish()
+    }
 }
 
 /// A set of variables to watch on behalf of a [constraint
 /// object](crate::state::State::constrain()).
 ///
+/// By default the constraint is attempted again as soon as any one of the
+/// variables is bound. A constraint that can't do anything until every
+/// variable is resolved can ask to wait for [all of them](VarWatch::all_of())
+/// instead, which saves re-attempting it each time one of them is bound.
+///
 /// Consider generating this with the [`resolve_1`], [`resolve_2`], [`OneOfTwo`]
 /// or [`TwoOfThree`] helpers.
 #[derive(Debug)]
-pub struct VarWatch(pub(crate) Vec<LVarId>);
+pub struct VarWatch {
+    pub(crate) vars: Vec<VarInfo>,
+    pub(crate) wake: Wake,
+}
+
+/// When a watching constraint should be attempted again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Wake {
+    /// As soon as any of the variables is bound.
+    Any,
+    /// Once all of the variables are resolved to a value.
+    All,
+}
 
 impl VarWatch {
     /// Watch one [`LVar`] for changes in a [`Constraint`].
     pub fn one<A>(a: LVar<A>) -> Self {
-        VarWatch(vec![a.id])
+        VarWatch::any_of(vec![VarInfo::of(&a)])
     }
 
-    /// Watch two [`LVar`]s for changes in a [`Constraint`].
+    /// Watch two [`LVar`]s for changes in a [`Constraint`], waking when either
+    /// one is bound.
     pub fn two<A, B>(a: LVar<A>, b: LVar<B>) -> Self {
-        VarWatch(vec![a.id, b.id])
+        VarWatch::any_of(vec![VarInfo::of(&a), VarInfo::of(&b)])
+    }
+
+    /// Watch two [`LVar`]s for changes in a [`Constraint`], waking only once
+    /// both are resolved.
+    pub fn both<A, B>(a: LVar<A>, b: LVar<B>) -> Self {
+        VarWatch::all_of(vec![VarInfo::of(&a), VarInfo::of(&b)])
+    }
+
+    /// Wake the [`Constraint`] as soon as any of these variables is bound.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{var, LVar};
+    /// use canrun::state::constraints::VarWatch;
+    ///
+    /// let (x, y): (LVar<i32>, LVar<String>) = (var(), var());
+    /// let watch = VarWatch::any_of(vec![x.into(), y.into()]);
+    /// ```
+    pub fn any_of(vars: Vec<VarInfo>) -> Self {
+        VarWatch {
+            vars,
+            wake: Wake::Any,
+        }
+    }
+
+    /// Wake the [`Constraint`] only once all of these variables are resolved
+    /// to a value.
+    ///
+    /// If one of the variables is unified with another unbound variable, the
+    /// constraint waits on that variable instead.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{var, LVar};
+    /// use canrun::state::constraints::VarWatch;
+    ///
+    /// let (x, y): (LVar<i32>, LVar<String>) = (var(), var());
+    /// let watch = VarWatch::all_of(vec![x.into(), y.into()]);
+    /// ```
+    pub fn all_of(vars: Vec<VarInfo>) -> Self {
+        VarWatch {
+            vars,
+            wake: Wake::All,
+        }
+    }
+
+    /// The variables being watched.
+    pub fn vars(&self) -> &[VarInfo] {
+        &self.vars
     }
 }
 
@@ -133,6 +382,7 @@ where
     let b = state.resolve_val(b);
     match (a, b) {
         (Resolved(a), Resolved(b)) => Ok((a.clone(), b.clone())),
+        (Var(a), Var(b)) => Err(VarWatch::both(*a, *b)),
         (Var(var), _) => Err(VarWatch::one(*var)),
         (_, Var(var)) => Err(VarWatch::one(*var)),
     }
@@ -196,6 +446,8 @@ impl<A: Debug, B: Debug, C: Debug> TwoOfThree<A, B, C> {
             (Resolved(a), Resolved(b), c) => Ok(TwoOfThree::AB(a.clone(), b.clone(), c.clone())),
             (a, Resolved(b), Resolved(c)) => Ok(TwoOfThree::BC(a.clone(), b.clone(), c.clone())),
             (Resolved(a), b, Resolved(c)) => Ok(TwoOfThree::AC(a.clone(), b.clone(), c.clone())),
+            // One more binding may be all that's needed, so these can't
+            // wait for both vars.
             (Var(a), Var(b), _) => Err(VarWatch::two(*a, *b)),
             (Var(a), _, Var(c)) => Err(VarWatch::two(*a, *c)),
             (_, Var(b), Var(c)) => Err(VarWatch::two(*b, *c)),
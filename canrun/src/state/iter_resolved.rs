@@ -1,6 +1,10 @@
+use super::observer::Event;
+use super::residual::ResidualState;
 use super::resolved::ResolvedState;
+use super::search::{DepthFirst, Interleave, SearchStrategy};
 use super::State;
 use crate::domains::Domain;
+use std::iter::empty;
 
 /// An Iterator of [`ResolvedStates`](crate::state::ResolvedState).
 ///
@@ -8,11 +12,45 @@ use crate::domains::Domain;
 /// [`.iter_resolved()`](IterResolved::iter_resolved()) trait.
 pub type ResolvedStateIter<'s, D> = Box<dyn Iterator<Item = ResolvedState<D>> + 's>;
 
+/// An Iterator of [`ResidualStates`](crate::state::ResidualState).
+///
+/// Typically obtained through the
+/// [`.iter_residual()`](IterResolved::iter_residual()) trait.
+pub type ResidualStateIter<'s, D> = Box<dyn Iterator<Item = ResidualState<D>> + 's>;
+
 /// Iterate over [`ResolvedStates`](crate::state::ResolvedState).
 ///
 /// This trait is implemented on the typical values that contain or represent an
 /// open state, such as [`Goal`](crate::goals::Goal) and of course
 /// [`State`](crate::state::State) itself.
+///
+/// Only [`.iter_resolved_with()`](IterResolved::iter_resolved_with()) needs
+/// to be implemented, so that every [search strategy](crate::search) (and the
+/// [budgets](crate::budget) enforced by one) is honored. Override
+/// [`.iter_residual_with()`](IterResolved::iter_residual_with()) to support
+/// [residual states](IterResolved::iter_residual()).
+///
+/// # Example:
+/// ```
+/// use canrun::{Goal, IterResolved, Query, ResolvedStateIter, unify, var};
+/// use canrun::search::SearchStrategy;
+/// use canrun::example::I32;
+///
+/// struct Wrapper<'a>(Goal<'a, I32>);
+///
+/// impl<'a> IterResolved<'a, I32> for Wrapper<'a> {
+///     fn iter_resolved_with<S>(self, strategy: S) -> ResolvedStateIter<'a, I32>
+///     where
+///         S: SearchStrategy<'a, I32>,
+///     {
+///         self.0.iter_resolved_with(strategy)
+///     }
+/// }
+///
+/// let x = var();
+/// let results: Vec<_> = Wrapper(unify(x, 1)).query(x).collect();
+/// assert_eq!(results, vec![1]);
+/// ```
 pub trait IterResolved<'a, D: Domain<'a> + 'a> {
     /// Get an iterator of all valid, [resolved
     /// states](crate::state::ResolvedState) that can be derived.
@@ -36,23 +74,163 @@ pub trait IterResolved<'a, D: Domain<'a> + 'a> {
     ///     .unify(&val!(x), &val!(1));
     /// let results: Vec<ResolvedState<I32>> = state.iter_resolved().collect();
     /// ```
-    fn iter_resolved(self) -> ResolvedStateIter<'a, D>;
+    fn iter_resolved(self) -> ResolvedStateIter<'a, D>
+    where
+        Self: Sized,
+    {
+        self.iter_resolved_with(DepthFirst)
+    }
+
+    /// Get an iterator of all valid, [resolved
+    /// states](crate::state::ResolvedState) that can be derived, fairly
+    /// interleaving the branches of every [fork](crate::state::State::fork()).
+    ///
+    /// [`.iter_resolved()`](IterResolved::iter_resolved()) explores each
+    /// branch completely before moving on to the next, which means that a
+    /// branch with infinitely many (or infinitely deep) alternatives will
+    /// starve any that come after it. This version takes one step from each
+    /// branch in turn, in the style of miniKanren's interleaving streams.
+    /// Results may come out in a different order.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, IterResolved, any, either, lazy, unify, var, LVar};
+    /// use canrun::example::I32;
+    ///
+    /// fn forever<'a>(x: LVar<i32>) -> Goal<'a, I32> {
+    ///     lazy(move || either(forever(x), unify(x, 0)))
+    /// }
+    ///
+    /// let x = var();
+    /// let goal: Goal<I32> = any![forever(x), unify(x, 1)];
+    /// assert!(goal.iter_resolved_interleaved().nth(1).is_some());
+    /// ```
+    fn iter_resolved_interleaved(self) -> ResolvedStateIter<'a, D>
+    where
+        Self: Sized,
+    {
+        self.iter_resolved_with(Interleave)
+    }
+
+    /// Get an iterator of all valid, [resolved
+    /// states](crate::state::ResolvedState) that can be derived, using a
+    /// specific [search strategy](crate::search) to explore the pending
+    /// [forks](crate::state::State::fork()).
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, IterResolved, ResolvedState, any, unify, var};
+    /// use canrun::search::BreadthFirst;
+    /// use canrun::example::I32;
+    ///
+    /// let x = var();
+    /// let goal: Goal<I32> = any![unify(x, 1), unify(x, 2)];
+    /// let results: Vec<ResolvedState<I32>> = goal.iter_resolved_with(BreadthFirst).collect();
+    /// assert_eq!(results.len(), 2);
+    /// ```
+    fn iter_resolved_with<S>(self, strategy: S) -> ResolvedStateIter<'a, D>
+    where
+        Self: Sized,
+        S: SearchStrategy<'a, D>;
+
+    /// Get an iterator of all valid states that can be derived, including
+    /// those with [constraints](crate::state::State::constrain()) that are
+    /// still waiting on unresolved variables.
+    ///
+    /// [`.iter_resolved()`](IterResolved::iter_resolved()) silently discards
+    /// these states. Here they are returned as a
+    /// [`ResidualState`](crate::state::ResidualState) that describes each
+    /// [pending constraint](crate::state::PendingConstraint).
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, IterResolved, lt, var};
+    /// use canrun::example::I32;
+    ///
+    /// let (x, y) = (var(), var());
+    /// let goal: Goal<I32> = lt(x, y);
+    /// let results: Vec<_> = goal.iter_residual().collect();
+    /// assert_eq!(results.len(), 1);
+    /// assert_eq!(results[0].constraints().len(), 1);
+    /// ```
+    fn iter_residual(self) -> ResidualStateIter<'a, D>
+    where
+        Self: Sized,
+    {
+        self.iter_residual_with(DepthFirst)
+    }
+
+    /// Get an iterator of all valid states that can be derived, including
+    /// those with pending constraints, using a specific [search
+    /// strategy](crate::search).
+    ///
+    /// See [`.iter_residual()`](IterResolved::iter_residual()) for details.
+    ///
+    /// The default can't see pending constraints, so it only returns the
+    /// states from [`.iter_resolved_with()`](IterResolved::iter_resolved_with()).
+    fn iter_residual_with<S>(self, strategy: S) -> ResidualStateIter<'a, D>
+    where
+        Self: Sized,
+        S: SearchStrategy<'a, D>,
+    {
+        Box::new(
+            self.iter_resolved_with(strategy)
+                .map(|resolved| ResidualState {
+                    resolved,
+                    constraints: Vec::new(),
+                }),
+        )
+    }
 }
 
 impl<'a, D: Domain<'a> + 'a> IterResolved<'a, D> for State<'a, D> {
-    fn iter_resolved(self) -> ResolvedStateIter<'a, D> {
-        Box::new(self.iter_forks().filter_map(|s: State<'a, D>| {
+    fn iter_resolved_with<S>(self, strategy: S) -> ResolvedStateIter<'a, D>
+    where
+        S: SearchStrategy<'a, D>,
+    {
+        Box::new(strategy.search(self).filter_map(|s: State<'a, D>| {
             if s.constraints.is_empty() {
+                s.observe(&Event::Solution);
                 Some(ResolvedState { domain: s.domain })
             } else {
+                s.observe(&Event::DeadEnd);
                 None
             }
         }))
     }
+
+    fn iter_residual_with<S>(self, strategy: S) -> ResidualStateIter<'a, D>
+    where
+        S: SearchStrategy<'a, D>,
+    {
+        Box::new(strategy.search(self).map(|s: State<'a, D>| {
+            let constraints = s.pending_constraints();
+            ResidualState {
+                resolved: ResolvedState { domain: s.domain },
+                constraints,
+            }
+        }))
+    }
 }
 
 impl<'a, D: Domain<'a> + 'a> IterResolved<'a, D> for Option<State<'a, D>> {
-    fn iter_resolved(self) -> ResolvedStateIter<'a, D> {
-        Box::new(self.into_iter().flat_map(State::iter_resolved))
+    fn iter_resolved_with<S>(self, strategy: S) -> ResolvedStateIter<'a, D>
+    where
+        S: SearchStrategy<'a, D>,
+    {
+        match self {
+            Some(state) => state.iter_resolved_with(strategy),
+            None => Box::new(empty()),
+        }
+    }
+
+    fn iter_residual_with<S>(self, strategy: S) -> ResidualStateIter<'a, D>
+    where
+        S: SearchStrategy<'a, D>,
+    {
+        match self {
+            Some(state) => state.iter_residual_with(strategy),
+            None => Box::new(empty()),
+        }
     }
 }
@@ -0,0 +1,491 @@
+//! Watch what a [`State`](crate::State) does while it searches for results.
+//!
+//! An [`Observer`] attached with
+//! [`State::with_observer()`](crate::State::with_observer()) or
+//! [`Goal::with_observer()`](crate::Goal::with_observer()) is told about every
+//! [`Event`] in the search: unifications, constraint attempts, variable
+//! watches, forks, dead ends and the resolved states that come out the other
+//! end. Each
+//! event comes with the number of forks that were expanded to reach it, which
+//! makes it easy to print the search as a tree.
+//!
+//! [`LogObserver`] writes an indented trace through the
+//! [`log`](https://docs.rs/log) crate, and
+//! [`QueryStats`](crate::stats::QueryStats) counts them.
+//!
+//! # Example:
+//! ```
+//! use canrun::{Goal, any, unify, var, LVar};
+//! use canrun::observer::LogObserver;
+//! use canrun::example::I32;
+//! use canrun::shared::Rc;
+//!
+//! let x: LVar<i32> = LVar::labeled("x");
+//! let goal: Goal<I32> = any![unify(x, 1), unify(x, 2)];
+//! let results: Vec<_> = goal
+//!     .with_observer(Rc::new(LogObserver::new()))
+//!     .query(x)
+//!     .collect();
+//! assert_eq!(results, vec![1, 2]);
+//! ```
+
+use crate::shared::{Rc, Shareable};
+use crate::value::{LVar, LVarId};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// A [logic variable](crate::value::LVar) mentioned in an [`Event`].
+///
+/// Like `LVar`, two `VarInfo`s are equal if they refer to the same variable,
+/// regardless of their labels.
+#[derive(Clone, Copy)]
+pub struct VarInfo {
+    id: LVarId,
+    label: Option<&'static str>,
+}
+
+impl VarInfo {
+    pub(crate) fn of<T>(var: &LVar<T>) -> Self {
+        VarInfo {
+            id: var.id,
+            label: var.label,
+        }
+    }
+
+    /// The internal id of the variable.
+    ///
+    /// As with the `LVar` `Debug` output, this should only be used for
+    /// debugging purposes.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// The label given to [`LVar::labeled()`](crate::value::LVar::labeled()),
+    /// if any.
+    pub fn label(&self) -> Option<&'static str> {
+        self.label
+    }
+}
+
+impl<T> From<LVar<T>> for VarInfo {
+    fn from(var: LVar<T>) -> Self {
+        VarInfo::of(&var)
+    }
+}
+
+impl PartialEq for VarInfo {
+    fn eq(&self, other: &VarInfo) -> bool {
+        self.id == other.id
+    }
+}
+impl Eq for VarInfo {}
+
+impl Hash for VarInfo {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl fmt::Debug for VarInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.label {
+            Some(label) => write!(f, "{}/{}", self.id, label),
+            None => write!(f, "{}", self.id),
+        }
+    }
+}
+
+impl fmt::Display for VarInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Something that happened while searching, as reported to an [`Observer`].
+///
+/// The `Display` implementation gives a short, single line description.
+#[derive(Debug)]
+pub enum Event<'e> {
+    /// Two values were [unified](crate::State::unify()).
+    ///
+    /// Reported once the unification has finished, after any events it caused
+    /// such as unifying the values inside a structure or re-running
+    /// constraints.
+    Unify {
+        /// The left hand value, as passed in.
+        a: &'e dyn fmt::Debug,
+        /// The right hand value, as passed in.
+        b: &'e dyn fmt::Debug,
+        /// Whether the values could be unified.
+        succeeded: bool,
+    },
+    /// A [constraint](crate::State::constrain()) had everything it needed and
+    /// was checked.
+    ///
+    /// Like [`Event::Unify`], this is reported after any events it caused.
+    Constraint {
+        /// The constraint that was checked.
+        constraint: &'e dyn fmt::Debug,
+        /// Whether the constraint was satisfied.
+        succeeded: bool,
+    },
+    /// A [constraint](crate::State::constrain()) could not be checked yet, and
+    /// will be attempted again once one of these variables is bound.
+    Watch {
+        /// The waiting constraint.
+        constraint: &'e dyn fmt::Debug,
+        /// The variables being watched.
+        vars: &'e [VarInfo],
+        /// Whether the constraint was already waiting and has been woken by a
+        /// binding, only to be registered again.
+        woken: bool,
+    },
+    /// A [constraint](crate::State::constrain()) could not be checked yet,
+    /// and was dropped since one already waiting is
+    /// [identical](crate::state::constraints::Constraint::key()) or
+    /// [subsumes](crate::state::constraints::Constraint::subsumes()) it.
+    Redundant {
+        /// The dropped constraint.
+        constraint: &'e dyn fmt::Debug,
+        /// Whether the constraint was already waiting and has been woken by a
+        /// binding.
+        woken: bool,
+    },
+    /// A [fork](crate::State::fork()) was added to the state.
+    ForkPushed {
+        /// The new fork.
+        fork: &'e dyn fmt::Debug,
+        /// The number of forks now waiting in the state, including this one.
+        pending: usize,
+    },
+    /// A pending fork was expanded into alternate states.
+    ///
+    /// Events in those states are reported one level deeper.
+    ForkExpanded {
+        /// The fork being expanded.
+        fork: &'e dyn fmt::Debug,
+        /// The [`size_hint`](crate::state::Fork::size_hint()) of the fork.
+        size_hint: Option<usize>,
+    },
+    /// A fully [resolved state](crate::ResolvedState) was found.
+    Solution,
+    /// A branch of the search ended without a solution.
+    ///
+    /// This is reported for each alternative of an expanded fork that failed,
+    /// as far as the fork's [`size_hint`](crate::state::Fork::size_hint())
+    /// can tell, and for each state that ran out of forks while constraints
+    /// were still waiting.
+    DeadEnd,
+    /// The state was cloned, typically to try another alternative of a fork.
+    StateCloned,
+}
+
+impl<'e> fmt::Display for Event<'e> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::Unify { a, b, succeeded } => {
+                write!(f, "unify {:?} = {:?}", a, b)?;
+                if !succeeded {
+                    write!(f, " failed")?;
+                }
+                Ok(())
+            }
+            Event::Constraint {
+                constraint,
+                succeeded,
+            } => {
+                let outcome = if *succeeded { "satisfied" } else { "failed" };
+                write!(f, "constraint {:?} {}", constraint, outcome)
+            }
+            Event::Watch {
+                constraint,
+                vars,
+                woken,
+            } => {
+                write!(f, "constraint {:?} watching {:?}", constraint, vars)?;
+                if *woken {
+                    write!(f, " again")?;
+                }
+                Ok(())
+            }
+            Event::Redundant { constraint, .. } => {
+                write!(f, "constraint {:?} dropped as redundant", constraint)
+            }
+            Event::ForkPushed { fork, .. } => write!(f, "fork {:?}", fork),
+            Event::ForkExpanded { fork, size_hint } => match size_hint {
+                Some(size) => write!(f, "expand {:?} into {} states", fork, size),
+                None => write!(f, "expand {:?}", fork),
+            },
+            Event::Solution => write!(f, "solution"),
+            Event::DeadEnd => write!(f, "dead end"),
+            Event::StateCloned => write!(f, "clone"),
+        }
+    }
+}
+
+/// Receives [`Events`](Event) from a [`State`](crate::State) during a search.
+///
+/// The `depth` is the number of forks that were expanded to reach the state
+/// that produced the event.
+///
+/// # Example:
+/// ```
+/// use canrun::{Goal, any, unify, var};
+/// use canrun::observer::{Event, Observer};
+/// use canrun::example::I32;
+/// use canrun::shared::Rc;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// #[derive(Default)]
+/// struct CountSolutions(AtomicUsize);
+///
+/// impl Observer for CountSolutions {
+///     fn observe(&self, _depth: usize, event: &Event<'_>) {
+///         if let Event::Solution = event {
+///             self.0.fetch_add(1, Ordering::Relaxed);
+///         }
+///     }
+/// }
+///
+/// let x = var();
+/// let observer = Rc::new(CountSolutions::default());
+/// let goal: Goal<I32> = any![unify(x, 1), unify(x, 2)];
+/// let results: Vec<_> = goal.with_observer(observer.clone()).query(x).collect();
+/// assert_eq!(observer.0.load(Ordering::Relaxed), results.len());
+/// ```
+pub trait Observer: Shareable {
+    /// Called for every event in the search.
+    fn observe(&self, depth: usize, event: &Event<'_>);
+}
+
+/// An [`Observer`] that writes an indented trace through the
+/// [`log`](https://docs.rs/log) crate.
+///
+/// Each event is logged at [`Level::Trace`](log::Level::Trace) by default,
+/// indented by two spaces for each expanded fork.
+///
+/// # Example:
+/// ```
+/// use canrun::observer::LogObserver;
+///
+/// let observer = LogObserver::new().level(log::Level::Debug);
+/// ```
+#[derive(Clone, Debug)]
+pub struct LogObserver {
+    level: log::Level,
+}
+
+impl LogObserver {
+    /// Create an observer that logs at [`Level::Trace`](log::Level::Trace).
+    pub fn new() -> Self {
+        LogObserver {
+            level: log::Level::Trace,
+        }
+    }
+
+    /// Log at a different [`Level`](log::Level).
+    pub fn level(self, level: log::Level) -> Self {
+        LogObserver { level }
+    }
+}
+
+impl Default for LogObserver {
+    fn default() -> Self {
+        LogObserver::new()
+    }
+}
+
+impl LogObserver {
+    // The trace line for an event, or `None` if it isn't worth logging.
+    fn line(depth: usize, event: &Event<'_>) -> Option<String> {
+        match event {
+            // Clones don't say much about the search, and would clutter the
+            // trace.
+            Event::StateCloned => None,
+            _ => Some(format!("{:indent$}{}", "", event, indent = depth * 2)),
+        }
+    }
+}
+
+impl Observer for LogObserver {
+    fn observe(&self, depth: usize, event: &Event<'_>) {
+        if !log::log_enabled!(self.level) {
+            return;
+        }
+        if let Some(line) = LogObserver::line(depth, event) {
+            log::log!(self.level, "{}", line);
+        }
+    }
+}
+
+// Every observer attached to a state. Attaching another one adds it to the
+// list, so that tracing part of a search doesn't hide it from the stats of an
+// enclosing goal.
+pub(crate) struct Observers<'a>(Vec<Rc<dyn Observer + 'a>>);
+
+impl<'a> Observers<'a> {
+    pub(crate) fn add(attached: Option<&Self>, observer: Rc<dyn Observer + 'a>) -> Self {
+        let mut observers = attached.map_or_else(Vec::new, |attached| attached.0.clone());
+        if !observers.iter().any(|other| Rc::ptr_eq(other, &observer)) {
+            observers.push(observer);
+        }
+        Observers(observers)
+    }
+}
+
+impl<'a> Observer for Observers<'a> {
+    fn observe(&self, depth: usize, event: &Event<'_>) {
+        for observer in &self.0 {
+            observer.observe(depth, event);
+        }
+    }
+}
+
+/// Report an [`Event::DeadEnd`] for each alternative of a fork that didn't
+/// make it out of the iterator, once it has been exhausted.
+pub(crate) fn report_dead_ends<'a, T: 'a>(
+    states: Box<dyn Iterator<Item = T> + 'a>,
+    observer: Rc<dyn Observer + 'a>,
+    depth: usize,
+    size_hint: Option<usize>,
+) -> Box<dyn Iterator<Item = T> + 'a> {
+    let mut states = states;
+    let mut produced = 0;
+    let mut size_hint = size_hint;
+    Box::new(std::iter::from_fn(move || match states.next() {
+        Some(state) => {
+            produced += 1;
+            Some(state)
+        }
+        None => {
+            for _ in produced..size_hint.take().unwrap_or(0) {
+                observer.observe(depth, &Event::DeadEnd);
+            }
+            None
+        }
+    }))
+}
+
+/// Lets a borrowed trait object (which may be unsized) stand in as a
+/// `&dyn Debug` inside an [`Event`].
+pub(crate) struct Described<'e, T: ?Sized>(pub(crate) &'e T);
+
+impl<'e, T: fmt::Debug + ?Sized> fmt::Debug for Described<'e, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Event, LogObserver, Observer};
+    use crate as canrun;
+    use crate::example::I32;
+    use crate::shared::Rc;
+    use crate::stats::QueryStats;
+    use crate::{all, any, lt, unify, var, Goal, IterResolved, LVar};
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Record(Mutex<Vec<(usize, String)>>);
+
+    impl Observer for Record {
+        fn observe(&self, depth: usize, event: &Event<'_>) {
+            self.0.lock().unwrap().push((depth, event.to_string()));
+        }
+    }
+
+    impl Record {
+        fn events(&self) -> Vec<(usize, String)> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    fn run(goal: Goal<'_, I32>) -> Vec<(usize, String)> {
+        let record = Rc::new(Record::default());
+        goal.with_observer(record.clone()).iter_resolved().count();
+        record.events()
+    }
+
+    #[test]
+    fn reports_forks_by_depth() {
+        let x: LVar<i32> = LVar::labeled("x");
+        let events = run(any![unify(x, 1), unify(x, 2)]);
+        assert!(events[0].1.starts_with("fork"));
+        assert!(events[1].1.starts_with("expand"));
+        assert_eq!(events[1].0, 0);
+        let unified: Vec<_> = events
+            .iter()
+            .filter(|(_, e)| e.starts_with("unify"))
+            .collect();
+        assert_eq!(unified.len(), 2);
+        assert!(unified
+            .iter()
+            .all(|(depth, e)| *depth == 1 && e.contains("/x")));
+        let solutions = events.iter().filter(|(_, e)| e == "solution").count();
+        assert_eq!(solutions, 2);
+    }
+
+    #[test]
+    fn reports_failed_unification() {
+        let x = var();
+        let events = run(all![unify(x, 1), unify(x, 2)]);
+        assert!(events.last().unwrap().1.ends_with("failed"));
+        assert!(events.iter().all(|(_, e)| e != "solution"));
+    }
+
+    #[test]
+    fn reports_constraints_and_watches() {
+        let (x, y): (LVar<i32>, LVar<i32>) = (LVar::labeled("x"), LVar::labeled("y"));
+        let events = run(all![lt(x, y), unify(x, 1), unify(y, 2)]);
+        let watch = events.iter().find(|(_, e)| e.contains("watching")).unwrap();
+        assert!(watch.1.contains("/x") || watch.1.contains("/y"));
+        assert!(events.iter().any(|(_, e)| e.ends_with("satisfied")));
+    }
+
+    #[test]
+    fn observers_are_combined() {
+        let x = var();
+        let record = Rc::new(Record::default());
+        let stats = QueryStats::new();
+        let goal: Goal<I32> = unify(x, 1).with_observer(record.clone()).with_stats(&stats);
+        goal.iter_resolved().count();
+        assert_eq!(stats.unifications(), 1);
+        assert_eq!(stats.solutions(), 1);
+        assert!(record.events().iter().any(|(_, e)| e == "solution"));
+    }
+
+    #[test]
+    fn observers_are_only_attached_once() {
+        let x = var();
+        let stats = QueryStats::new();
+        let goal: Goal<I32> = unify(x, 1).with_stats(&stats);
+        Goal::all(vec![goal.clone(), goal]).iter_resolved().count();
+        assert_eq!(stats.unifications(), 2);
+    }
+
+    // Collects the lines a `LogObserver` would write, without going through
+    // the global logger.
+    #[derive(Default)]
+    struct Lines(Mutex<Vec<String>>);
+
+    impl Observer for Lines {
+        fn observe(&self, depth: usize, event: &Event<'_>) {
+            if let Some(line) = LogObserver::line(depth, event) {
+                self.0.lock().unwrap().push(line);
+            }
+        }
+    }
+
+    #[test]
+    fn log_observer_writes_an_indented_trace() {
+        let x = var();
+        let lines = Rc::new(Lines::default());
+        let goal: Goal<I32> = any![unify(x, 1), unify(x, 2)];
+        goal.with_observer(lines.clone()).iter_resolved().count();
+        let lines = lines.0.lock().unwrap().clone();
+        assert!(lines.iter().any(|line| line.starts_with("expand")));
+        assert!(lines.iter().any(|line| line.starts_with("  unify")));
+        assert!(lines.iter().all(|line| line.trim() != "clone"));
+    }
+}
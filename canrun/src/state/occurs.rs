@@ -0,0 +1,157 @@
+use super::State;
+use crate::domains::{Domain, DomainType};
+use crate::value::{
+    LVarId, Val,
+    Val::{Resolved, Var},
+};
+use crate::UnifyIn;
+use std::fmt::Debug;
+
+/// Looks for a [logic variable](crate::value::LVar) inside the value it is
+/// about to be bound to.
+///
+/// Only used when the [occurs check](State::with_occurs_check()) is enabled.
+/// Structures that contain other [`Vals`](crate::value::Val) take part by
+/// passing each of them to [`.val()`](OccursCheck::val()) from
+/// [`UnifyIn::occurs_check()`](crate::UnifyIn::occurs_check()).
+///
+/// # Example:
+/// ```
+/// use canrun::{Goal, IterResolved, State, UnifyIn, OccursCheck, DomainType, Val, unify, val, var};
+/// use canrun::shared::Rc;
+///
+/// #[derive(Debug)]
+/// enum List {
+///     Nil,
+///     Cons(Val<i32>, Val<List>),
+/// }
+///
+/// canrun::domain! {
+///     Lists { i32, List }
+/// }
+///
+/// impl<'a, D: DomainType<'a, i32> + DomainType<'a, List>> UnifyIn<'a, D> for List {
+///     fn unify_resolved(state: State<'a, D>, a: Rc<Self>, b: Rc<Self>) -> Option<State<'a, D>> {
+///         match (&*a, &*b) {
+///             (List::Nil, List::Nil) => Some(state),
+///             (List::Cons(a, a_tail), List::Cons(b, b_tail)) => {
+///                 state.unify(a, b)?.unify(a_tail, b_tail)
+///             }
+///             _ => None,
+///         }
+///     }
+///
+///     fn occurs_check(&self, check: &mut OccursCheck<'_, 'a, D>) {
+///         if let List::Cons(head, tail) = self {
+///             check.val(head);
+///             check.val(tail);
+///         }
+///     }
+/// }
+///
+/// let x = var();
+/// let cyclic = List::Cons(val!(1), val!(x));
+/// let goal: Goal<Lists> = unify(x, cyclic).with_occurs_check();
+/// assert_eq!(goal.iter_resolved().count(), 0);
+/// ```
+pub struct OccursCheck<'s, 'a, D: Domain<'a> + 'a> {
+    state: &'s State<'a, D>,
+    var: LVarId,
+    found: bool,
+}
+
+impl<'s, 'a, D: Domain<'a> + 'a> OccursCheck<'s, 'a, D> {
+    pub(super) fn new(state: &'s State<'a, D>, var: LVarId) -> Self {
+        OccursCheck {
+            state,
+            var,
+            found: false,
+        }
+    }
+
+    /// Check a value contained in the structure being bound.
+    ///
+    /// Variables are resolved in the current state, and resolved values are
+    /// searched recursively.
+    pub fn val<T>(&mut self, val: &Val<T>)
+    where
+        T: UnifyIn<'a, D> + Debug,
+        D: DomainType<'a, T>,
+    {
+        if self.found {
+            return;
+        }
+        match self.state.resolve_val(val) {
+            Var(var) => self.found = var.id == self.var,
+            Resolved(value) => value.occurs_check(self),
+        }
+    }
+
+    pub(super) fn found(&self) -> bool {
+        self.found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate as canrun;
+    use crate::lvec::{lvec, LVec};
+    use crate::shared::Rc;
+    use crate::{
+        all, unify, val, var, DomainType, Goal, IterResolved, OccursCheck, State, UnifyIn, Val,
+    };
+
+    #[derive(Debug)]
+    struct Tree(Val<LVec<Tree>>);
+
+    canrun::domain! {
+        Trees { Tree, LVec<Tree> }
+    }
+
+    impl<'a, D> UnifyIn<'a, D> for Tree
+    where
+        D: DomainType<'a, Tree> + DomainType<'a, LVec<Tree>>,
+    {
+        fn unify_resolved(state: State<'a, D>, a: Rc<Self>, b: Rc<Self>) -> Option<State<'a, D>> {
+            state.unify(&a.0, &b.0)
+        }
+
+        fn occurs_check(&self, check: &mut OccursCheck<'_, 'a, D>) {
+            check.val(&self.0);
+        }
+    }
+
+    fn leaf() -> Tree {
+        Tree(val!(LVec::new()))
+    }
+
+    #[test]
+    fn cycles_are_allowed_by_default() {
+        let x = var();
+        let goal: Goal<Trees> = unify(x, Tree(val!(lvec![leaf(), x])));
+        assert_eq!(goal.iter_resolved().count(), 1);
+    }
+
+    #[test]
+    fn direct_cycle_fails() {
+        let x = var();
+        let goal: Goal<Trees> = unify(x, Tree(val!(lvec![leaf(), x]))).with_occurs_check();
+        assert_eq!(goal.iter_resolved().count(), 0);
+    }
+
+    #[test]
+    fn cycle_through_bound_var_fails() {
+        let (x, y) = (var(), var());
+        let goal: Goal<Trees> =
+            all![unify(y, lvec![x]), unify(x, Tree(val!(y)))].with_occurs_check();
+        assert_eq!(goal.iter_resolved().count(), 0);
+    }
+
+    #[test]
+    fn acyclic_structures_succeed() {
+        let (x, y) = (var(), var());
+        let goal: Goal<Trees> =
+            all![unify(y, leaf()), unify(x, Tree(val!(lvec![y, leaf()])))].with_occurs_check();
+        assert_eq!(goal.iter_resolved().count(), 1);
+    }
+}
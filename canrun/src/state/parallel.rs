@@ -0,0 +1,408 @@
+//! Explore the branches of forks on a work-stealing thread pool.
+//!
+//! Requires the `parallel` cargo feature, which also enables `sync`.
+//!
+//! Every time a [fork](crate::state::Fork) is expanded, the resulting
+//! branches are handed to a [rayon](https://docs.rs/rayon) thread pool, so that
+//! independent choices (such as the alternatives of an
+//! [`any`](crate::goals::any!) or the candidate positions of a
+//! [`member`](crate::lvec::member())) are searched concurrently.
+//!
+//! Branches are taken from each fork a chunk at a time, one for each thread
+//! in the pool, so a fork with a lazy or unbounded number of branches is only
+//! expanded as far as the search gets.
+//!
+//! Results can be returned in one of two [orders](Order):
+//! - [`Order::Deterministic`] yields results in the same order as the default
+//!   [depth first](crate::search::DepthFirst) search. Every result below a
+//!   chunk of branches is found before any of them is returned.
+//! - [`Order::Completion`] yields results as soon as they are found. The order
+//!   may differ from run to run.
+//!
+//! If a goal panics on one of the pool's threads, the panic is passed on to
+//! the thread reading the results.
+//!
+//! Only [`Goal<'static, D>`](crate::Goal) and [`State<'static,
+//! D>`](crate::State) can be searched in parallel, as the work may outlive the
+//! calling stack frame.
+//!
+//! # Example:
+//! ```
+//! use canrun::{Goal, any, unify, var};
+//! use canrun::parallel::Order;
+//! use canrun::example::I32;
+//!
+//! let x = var();
+//! let goal: Goal<I32> = any![unify(x, 1), unify(x, 2), unify(x, 3)];
+//! let result: Vec<_> = goal.query_parallel(Order::Deterministic, x).collect();
+//! assert_eq!(result, vec![1, 2, 3])
+//! ```
+
+use super::observer::Event;
+use super::resolved::ResolvedState;
+use super::search::Expansion;
+use super::{ResolvedStateIter, State, StateIter};
+use crate::domains::Domain;
+use crate::goals::Goal;
+use crate::ReifyIn;
+use rayon::prelude::*;
+use std::any::Any;
+use std::iter::{empty, from_fn, once};
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::Arc;
+
+/// The order in which the results of a parallel search are returned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Order {
+    /// Return results in the same order as a sequential depth first search.
+    ///
+    /// Each chunk of branches is explored completely before its results are
+    /// returned. Below the outermost fork, that means a fork with unbounded
+    /// branches will never return.
+    Deterministic,
+    /// Return results as soon as they are found, in no particular order.
+    ///
+    /// The pool stops searching while a few results are waiting to be read.
+    /// When used from inside the pool (such as within a rayon `par_iter`),
+    /// this falls back to [`Order::Deterministic`], since waiting for results
+    /// there could hold up the thread the search needs.
+    Completion,
+}
+
+/// Iterate over [`ResolvedStates`](crate::state::ResolvedState) found by
+/// exploring forks in parallel.
+///
+/// This is the parallel counterpart to
+/// [`IterResolved`](crate::state::IterResolved), implemented for
+/// `'static` [`Goals`](crate::Goal) and [`States`](crate::State).
+///
+/// # NOTE:
+/// A branch is searched to the end before its chunk of results is returned
+/// in [`Order::Deterministic`], so a fork with infinitely many branches (or
+/// an infinitely deep one) anywhere but at the outermost level will hang.
+/// [`Order::Completion`] streams results out of such a fork, but like a
+/// [depth first](crate::search::DepthFirst) search it never moves on to the
+/// branches after it. Use a [fair search](crate::search::Interleave) if every
+/// branch needs a turn.
+pub trait IterResolvedParallel<D: Domain<'static> + 'static>: Sized {
+    /// Get an iterator of all valid, [resolved
+    /// states](crate::state::ResolvedState) that can be derived, exploring
+    /// the branches of each fork in parallel.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, ResolvedState, any, unify, var};
+    /// use canrun::parallel::{IterResolvedParallel, Order};
+    /// use canrun::example::I32;
+    ///
+    /// let x = var();
+    /// let goal: Goal<I32> = any![unify(x, 1), unify(x, 2)];
+    /// let results: Vec<ResolvedState<I32>> = goal.iter_resolved_parallel(Order::Completion).collect();
+    /// assert_eq!(results.len(), 2);
+    /// ```
+    fn iter_resolved_parallel(self, order: Order) -> ResolvedStateIter<'static, D>;
+
+    /// Get [reified](crate::value::ReifyIn) results, exploring the branches of
+    /// each fork in parallel.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, any, unify, var};
+    /// use canrun::parallel::{IterResolvedParallel, Order};
+    /// use canrun::example::I32;
+    ///
+    /// let x = var();
+    /// let goal: Goal<I32> = any![unify(x, 1), unify(x, 2)];
+    /// let mut result: Vec<_> = IterResolvedParallel::query_parallel(goal, Order::Completion, x).collect();
+    /// result.sort();
+    /// assert_eq!(result, vec![1, 2]);
+    /// ```
+    fn query_parallel<Q>(self, order: Order, query: Q) -> Box<dyn Iterator<Item = Q::Reified>>
+    where
+        Q: ReifyIn<'static, D> + 'static,
+    {
+        Box::new(
+            self.iter_resolved_parallel(order)
+                .filter_map(move |resolved| query.reify_in(&resolved)),
+        )
+    }
+}
+
+impl<D: Domain<'static> + 'static> IterResolvedParallel<D> for State<'static, D> {
+    fn iter_resolved_parallel(self, order: Order) -> ResolvedStateIter<'static, D> {
+        match order {
+            Order::Deterministic => Box::new(settle_ordered(self).filter_map(into_resolved)),
+            // Waiting on the results from inside the pool could take the
+            // thread the search needs, so search in order there instead.
+            Order::Completion if rayon::current_thread_index().is_some() => {
+                self.iter_resolved_parallel(Order::Deterministic)
+            }
+            Order::Completion => {
+                // Only buffer a few results, so that the pool waits for a
+                // slow reader instead of racing ahead.
+                let (found, results) = sync_channel(rayon::current_num_threads());
+                let stop = Arc::new(AtomicBool::new(false));
+                let worker_stop = stop.clone();
+                rayon::spawn(move || {
+                    // A panic would otherwise abort the process from inside
+                    // the thread pool. Hand it to the caller instead.
+                    let search = || settle_unordered(self, &found, &worker_stop);
+                    if let Err(panic) = catch_unwind(AssertUnwindSafe(search)) {
+                        let _ = found.send(Err(panic));
+                    }
+                });
+                Box::new(Unordered { results, stop })
+            }
+        }
+    }
+}
+
+impl<D: Domain<'static> + 'static> IterResolvedParallel<D> for Option<State<'static, D>> {
+    fn iter_resolved_parallel(self, order: Order) -> ResolvedStateIter<'static, D> {
+        match self {
+            Some(state) => state.iter_resolved_parallel(order),
+            None => Box::new(empty()),
+        }
+    }
+}
+
+impl<D: Domain<'static> + 'static> IterResolvedParallel<D> for Goal<'static, D> {
+    fn iter_resolved_parallel(self, order: Order) -> ResolvedStateIter<'static, D> {
+        self.apply(State::new()).iter_resolved_parallel(order)
+    }
+}
+
+impl<D: Domain<'static> + 'static> Goal<'static, D> {
+    /// Use the [query](crate::Query) interface to get an iterator of result
+    /// values, exploring the branches of each fork in parallel.
+    ///
+    /// See the [`parallel`](crate::parallel) module for details.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, any, unify, var};
+    /// use canrun::parallel::Order;
+    /// use canrun::example::I32;
+    ///
+    /// let x = var();
+    /// let goal: Goal<I32> = any![unify(x, 1), unify(x, 2)];
+    /// let result: Vec<_> = goal.query_parallel(Order::Deterministic, x).collect();
+    /// assert_eq!(result, vec![1, 2])
+    /// ```
+    pub fn query_parallel<Q>(self, order: Order, query: Q) -> Box<dyn Iterator<Item = Q::Reified>>
+    where
+        Q: ReifyIn<'static, D> + 'static,
+    {
+        IterResolvedParallel::query_parallel(self, order, query)
+    }
+}
+
+fn into_resolved<'a, D: Domain<'a> + 'a>(state: State<'a, D>) -> Option<ResolvedState<D>> {
+    if state.constraints.is_empty() {
+        state.observe(&Event::Solution);
+        Some(ResolvedState {
+            domain: state.domain,
+        })
+    } else {
+        state.observe(&Event::DeadEnd);
+        None
+    }
+}
+
+// Take branches from a fork a chunk at a time, so that one with unbounded
+// branches isn't drained before any of them are searched.
+fn chunks<I: Iterator>(mut branches: I) -> impl Iterator<Item = Vec<I::Item>> {
+    let size = rayon::current_num_threads();
+    from_fn(move || {
+        let chunk: Vec<_> = branches.by_ref().take(size).collect();
+        if chunk.is_empty() {
+            None
+        } else {
+            Some(chunk)
+        }
+    })
+}
+
+fn settle_ordered<D: Domain<'static> + 'static>(state: State<'static, D>) -> StateIter<'static, D> {
+    match state.expand() {
+        Expansion::Settled(state) => Box::new(once(state)),
+        Expansion::Forked(states) => Box::new(chunks(states).flat_map(|chunk| {
+            let settled: Vec<Vec<_>> = chunk
+                .into_par_iter()
+                .map(|state| settle_ordered(state).collect())
+                .collect();
+            settled.into_iter().flatten()
+        })),
+    }
+}
+
+fn settle_unordered<D: Domain<'static> + 'static>(
+    state: State<'static, D>,
+    found: &SyncSender<Found<D>>,
+    stop: &AtomicBool,
+) {
+    if stop.load(Ordering::Relaxed) {
+        return;
+    }
+    match state.expand() {
+        Expansion::Settled(state) => {
+            if let Some(resolved) = into_resolved(state) {
+                if found.send(Ok(resolved)).is_err() {
+                    stop.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+        Expansion::Forked(states) => {
+            for chunk in chunks(states) {
+                if stop.load(Ordering::Relaxed) {
+                    return;
+                }
+                chunk
+                    .into_par_iter()
+                    .for_each_with(found.clone(), |found, state| {
+                        settle_unordered(state, found, stop)
+                    });
+            }
+        }
+    }
+}
+
+// A result from the thread pool, or the payload of a panic in the search.
+type Found<D> = Result<ResolvedState<D>, Box<dyn Any + Send>>;
+
+/// Receives results from the thread pool, and tells it to stop searching once
+/// dropped.
+struct Unordered<D> {
+    results: Receiver<Found<D>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl<D> Iterator for Unordered<D> {
+    type Item = ResolvedState<D>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.results.recv() {
+            Ok(Ok(resolved)) => Some(resolved),
+            Ok(Err(panic)) => resume_unwind(panic),
+            Err(_) => None,
+        }
+    }
+}
+
+impl<D> Drop for Unordered<D> {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Order;
+    use crate as canrun;
+    use crate::example::I32;
+    use crate::state::{Fork, State, StateIter};
+    use crate::{any, both, custom, unify, val, var, Goal, LVar};
+
+    fn nested<'a>() -> (LVar<i32>, Goal<'a, I32>) {
+        let x = var();
+        let y = var();
+        let goal = both(
+            any![unify(y, 1), unify(y, 2), unify(y, 3)],
+            any![
+                both(unify(y, 1), any![unify(x, 1), unify(x, 2)]),
+                both(unify(y, 2), any![unify(x, 3), unify(x, 4)]),
+                both(unify(y, 3), any![unify(x, 5), unify(x, 6)]),
+            ],
+        );
+        (x, goal)
+    }
+
+    #[test]
+    fn deterministic_matches_depth_first() {
+        let (x, goal) = nested();
+        let sequential: Vec<_> = goal.clone().query(x).collect();
+        let parallel: Vec<_> = goal.query_parallel(Order::Deterministic, x).collect();
+        assert_eq!(sequential, vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(parallel, sequential);
+    }
+
+    #[test]
+    fn completion_finds_everything() {
+        let (x, goal) = nested();
+        let mut parallel: Vec<_> = goal.query_parallel(Order::Completion, x).collect();
+        parallel.sort();
+        assert_eq!(parallel, vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn completion_can_stop_early() {
+        let (x, goal) = nested();
+        let first = goal.query_parallel(Order::Completion, x).next();
+        assert!(first.is_some());
+    }
+
+    // A fork with a branch for every natural number.
+    #[derive(Debug)]
+    struct Naturals(LVar<i32>);
+
+    impl<'a> Fork<'a, I32> for Naturals {
+        fn fork(&self, state: State<'a, I32>) -> StateIter<'a, I32> {
+            let x = self.0;
+            Box::new((0..).filter_map(move |n| state.clone().unify(&val!(x), &val!(n))))
+        }
+    }
+
+    #[test]
+    fn deterministic_handles_unbounded_forks() {
+        let x = var();
+        let goal: Goal<I32> = Goal::fork(Naturals(x));
+        let first: Vec<_> = goal
+            .query_parallel(Order::Deterministic, x)
+            .take(3)
+            .collect();
+        assert_eq!(first, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn completion_handles_unbounded_forks() {
+        let x = var();
+        let goal: Goal<I32> = Goal::fork(Naturals(x));
+        let first: Vec<_> = goal.query_parallel(Order::Completion, x).take(3).collect();
+        assert_eq!(first.len(), 3);
+    }
+
+    #[test]
+    fn completion_works_inside_the_pool() {
+        let (x, goal) = nested();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+        let mut parallel: Vec<_> =
+            pool.install(|| goal.query_parallel(Order::Completion, x).collect());
+        parallel.sort();
+        assert_eq!(parallel, vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    fn panicking<'a>() -> (LVar<i32>, Goal<'a, I32>) {
+        let x = var();
+        let goal = any![unify(x, 1), custom(|_| panic!("goal panicked"))];
+        (x, goal)
+    }
+
+    #[test]
+    #[should_panic(expected = "goal panicked")]
+    fn deterministic_passes_on_panics() {
+        let (x, goal) = panicking();
+        goal.query_parallel(Order::Deterministic, x).for_each(drop);
+    }
+
+    #[test]
+    #[should_panic(expected = "goal panicked")]
+    fn completion_passes_on_panics() {
+        let (x, goal) = panicking();
+        goal.query_parallel(Order::Completion, x).for_each(drop);
+    }
+}
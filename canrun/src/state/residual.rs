@@ -0,0 +1,204 @@
+use super::observer::VarInfo;
+use super::resolved::ResolvedState;
+use super::Fork;
+use crate::domains::Domain;
+use crate::value::{LVar, ReifyIn};
+use std::fmt;
+
+/// A [constraint](crate::state::State::constrain()) that is still waiting
+/// for variables to be resolved.
+///
+/// Obtained through [`ResidualState`] and [`Residual`] once a search has
+/// finished, or from an open state with
+/// [`State::pending_constraints()`](crate::state::State::pending_constraints()).
+#[derive(Clone, PartialEq, Eq)]
+pub struct PendingConstraint {
+    description: String,
+    watching: Vec<VarInfo>,
+}
+
+impl PendingConstraint {
+    pub(crate) fn new<C: fmt::Debug + ?Sized>(constraint: &C, watching: Vec<VarInfo>) -> Self {
+        PendingConstraint {
+            description: format!("{:?}", constraint),
+            watching,
+        }
+    }
+
+    /// The `Debug` representation of the constraint.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// The variables the constraint is waiting on.
+    pub fn watching(&self) -> &[VarInfo] {
+        &self.watching
+    }
+
+    /// Return `true` if the constraint is waiting for this variable to be
+    /// resolved.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, lt, var};
+    /// use canrun::example::I32;
+    ///
+    /// let (x, y, z) = (var(), var(), var::<i32>());
+    /// let goal: Goal<I32> = lt(x, y);
+    /// let residual = goal.query_residual(x).next().unwrap();
+    /// assert!(residual.constraints[0].watches(x) || residual.constraints[0].watches(y));
+    /// assert!(!residual.constraints[0].watches(z));
+    /// ```
+    pub fn watches<T>(&self, var: LVar<T>) -> bool {
+        self.watching.contains(&VarInfo::of(&var))
+    }
+}
+
+impl fmt::Debug for PendingConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.description)
+    }
+}
+
+impl fmt::Display for PendingConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.description)
+    }
+}
+
+/// A [fork](crate::state::State::fork()) that has not been expanded yet.
+///
+/// Obtained through [`State::pending_forks()`](crate::state::State::pending_forks()).
+#[derive(Clone, PartialEq, Eq)]
+pub struct PendingFork {
+    description: String,
+    size_hint: Option<usize>,
+}
+
+impl PendingFork {
+    pub(crate) fn new<'a, D: Domain<'a>, F: Fork<'a, D> + ?Sized>(fork: &F) -> Self {
+        PendingFork {
+            description: format!("{:?}", fork),
+            size_hint: fork.size_hint(),
+        }
+    }
+
+    /// The `Debug` representation of the fork.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// The [`size_hint`](crate::state::Fork::size_hint()) of the fork.
+    pub fn size_hint(&self) -> Option<usize> {
+        self.size_hint
+    }
+}
+
+impl fmt::Debug for PendingFork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.description)
+    }
+}
+
+impl fmt::Display for PendingFork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.description)
+    }
+}
+
+/// A [`ResolvedState`] that may still have [pending
+/// constraints](PendingConstraint).
+///
+/// Obtained through
+/// [`.iter_residual()`](crate::IterResolved::iter_residual()). A state with no
+/// pending constraints is exactly what
+/// [`.iter_resolved()`](crate::IterResolved::iter_resolved()) would return.
+#[derive(Clone)]
+pub struct ResidualState<D> {
+    pub(crate) resolved: ResolvedState<D>,
+    pub(crate) constraints: Vec<PendingConstraint>,
+}
+
+impl<'a, D: Domain<'a> + 'a> ResidualState<D> {
+    /// Attempt to [reify](crate::value::ReifyIn) a value in this state.
+    ///
+    /// This will return `None` if the value depends on variables that are
+    /// still unresolved.
+    pub fn reify<T, R>(&self, value: T) -> Option<R>
+    where
+        T: ReifyIn<'a, D, Reified = R>,
+    {
+        value.reify_in(&self.resolved)
+    }
+
+    /// The constraints that could not be checked because some of the variables
+    /// they watch were never resolved.
+    pub fn constraints(&self) -> &[PendingConstraint] {
+        &self.constraints
+    }
+
+    /// Return `true` if there are no pending constraints.
+    pub fn is_resolved(&self) -> bool {
+        self.constraints.is_empty()
+    }
+}
+
+/// A [query](crate::Query) result that may still have [pending
+/// constraints](PendingConstraint).
+///
+/// Returned from [`.query_residual()`](crate::Query::query_residual()).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Residual<T> {
+    /// The reified query, or `None` if it depends on variables that are still
+    /// unresolved.
+    pub value: Option<T>,
+    /// The constraints that could not be checked because some of the variables
+    /// they watch were never resolved.
+    pub constraints: Vec<PendingConstraint>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate as canrun;
+    use crate::example::I32;
+    use crate::{all, any, lt, unify, var, Goal, IterResolved};
+
+    #[test]
+    fn unresolved_constraint_is_pending() {
+        let (x, y, z) = (var(), var(), var::<i32>());
+        let goal: Goal<I32> = all![unify(x, 1), lt(x, y)];
+        let results: Vec<_> = goal.query_residual(x).collect();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].value, Some(1));
+        assert_eq!(results[0].constraints.len(), 1);
+        assert!(results[0].constraints[0].watches(y));
+        assert!(!results[0].constraints[0].watches(z));
+    }
+
+    #[test]
+    fn resolved_goal_has_no_pending_constraints() {
+        let (x, y) = (var(), var());
+        let goal: Goal<I32> = all![unify(x, 1), unify(y, 2), lt(x, y)];
+        let results: Vec<_> = goal.iter_residual().collect();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_resolved());
+        assert_eq!(results[0].reify(y), Some(2));
+    }
+
+    #[test]
+    fn failing_goal_has_no_residuals() {
+        let (x, y) = (var(), var());
+        let goal: Goal<I32> = all![unify(x, 2), unify(y, 1), lt(x, y)];
+        assert_eq!(goal.query_residual(x).count(), 0);
+    }
+
+    #[test]
+    fn each_branch_has_its_own_residual() {
+        let (x, y) = (var(), var());
+        let goal: Goal<I32> = all![any![unify(x, 1), unify(y, 1)], lt(x, y)];
+        let results: Vec<_> = goal.query_residual((x, y)).collect();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.value.is_none()));
+        assert!(results.iter().all(|r| r.constraints.len() == 1));
+    }
+}
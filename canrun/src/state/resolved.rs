@@ -1,4 +1,5 @@
 use crate::domains::{Domain, DomainType};
+use crate::state::State;
 use crate::value::{ReifyIn, Val};
 use std::fmt::Debug;
 
@@ -8,12 +9,29 @@ use std::fmt::Debug;
 /// Calling [`.iter_resolved()`](crate::IterResolved::iter_resolved()) is the
 /// lowest level way to get an iterator of the possible resolved states, though
 /// the [`Query`](crate::Query) interface is quite a bit nicer.
+///
+/// With the `serde` feature enabled, a `ResolvedState` can be serialized and
+/// restored later, as long as its [domain](crate::domains::domain) derives
+/// `Serialize` and `Deserialize`. [`LVars`](crate::value::LVar) keep their
+/// ids, so the same variables can be reified from the restored state.
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ResolvedState<D> {
     pub(super) domain: D,
 }
 
 impl<'a, D: Domain<'a> + 'a> ResolvedState<D> {
+    // Whether two states bind every variable the same way, including any
+    // variables created along the way to either of them. Each state's
+    // bindings must already hold in the other.
+    pub(crate) fn same_bindings(&self, other: &Self) -> bool {
+        let holds_in = |a: &Self, b: &Self| match State::probe(&a.domain) {
+            Some(probe) => probe.already_unified(b.domain.bindings()),
+            None => false,
+        };
+        holds_in(self, other) && holds_in(other, self)
+    }
+
     pub(crate) fn resolve_val<'r, T>(&'r self, val: &'r Val<T>) -> &'r Val<T>
     where
         T: Debug,
@@ -49,3 +67,26 @@ impl<'a, D: Domain<'a> + 'a> ResolvedState<D> {
         value.reify_in(self)
     }
 }
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::ResolvedState;
+    use crate as canrun;
+    use crate::example::Collections;
+    use crate::lvec::{lvec, LVec};
+    use crate::{all, unify, var, Goal, IterResolved, LVar};
+
+    #[test]
+    fn serde_round_trip() {
+        let (x, y) = (var(), var());
+        let goal: Goal<Collections> = all![unify(x, lvec![1, y]), unify(y, 2)];
+        let state = goal.iter_resolved().next().unwrap();
+        let json = serde_json::to_string(&(state, x)).unwrap();
+
+        let (restored, x): (ResolvedState<Collections>, LVar<LVec<i32>>) =
+            serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.reify(x), Some(vec![1, 2]));
+        assert_eq!(restored.reify(y), Some(2));
+        assert_eq!(restored.reify(var::<i32>()), None);
+    }
+}
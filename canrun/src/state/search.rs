@@ -0,0 +1,390 @@
+//! Choose the order in which pending [forks](crate::state::Fork) are explored.
+//!
+//! [Forks](crate::state::State::fork()) are not evaluated as they are added.
+//! Instead, they are expanded lazily as the resulting
+//! [`ResolvedStates`](crate::state::ResolvedState) are requested. The order in
+//! which the branches of these forks are explored can have a large effect on
+//! how quickly results are found, and whether they are found at all.
+//!
+//! A [`SearchStrategy`] encapsulates that order. Several are provided:
+//!
+//! | Strategy               | Order |
+//! | --------               | ----- |
+//! | [`DepthFirst`]         | Explore each branch completely before moving on to the next. This is the default. |
+//! | [`BreadthFirst`]       | Explore every branch at one level before moving deeper. |
+//! | [`IterativeDeepening`] | Repeat a depth limited depth first search with increasing limits. |
+//! | [`Interleave`]         | Take one step from each branch in turn. |
+//!
+//! Strategies are typically selected through
+//! [`.query_with()`](crate::Query::query_with()) or
+//! [`.iter_resolved_with()`](crate::IterResolved::iter_resolved_with()).
+//!
+//! # Example:
+//! ```
+//! use canrun::{Goal, any, unify, var};
+//! use canrun::search::BreadthFirst;
+//! use canrun::example::I32;
+//!
+//! let x = var();
+//! let goal: Goal<I32> = any![any![unify(x, 1), unify(x, 2)], unify(x, 3)];
+//! let result: Vec<_> = goal.query_with(BreadthFirst, x).collect();
+//! assert_eq!(result, vec![3, 1, 2])
+//! ```
+
+use super::{State, StateIter};
+use crate::domains::Domain;
+use std::collections::VecDeque;
+use std::iter::once;
+
+/// Decide how the pending [forks](crate::state::Fork) of a [`State`] are
+/// expanded.
+///
+/// A strategy takes an open [`State`] and returns an iterator of every state
+/// that can be derived from it that has no remaining forks. These states may
+/// still have pending [constraints](crate::state::State::constrain()), which
+/// are checked later.
+///
+/// Custom strategies can be built on top of [`State::expand()`].
+///
+/// # Example:
+/// ```
+/// use canrun::{Domain, Goal, State, StateIter, any, unify, var};
+/// use canrun::search::{Expansion, SearchStrategy};
+/// use canrun::example::I32;
+///
+/// /// Only ever explore the first branch of each fork.
+/// struct FirstBranch;
+///
+/// impl<'a, D: Domain<'a> + 'a> SearchStrategy<'a, D> for FirstBranch {
+///     fn search(&self, state: State<'a, D>) -> StateIter<'a, D> {
+///         match state.expand() {
+///             Expansion::Settled(state) => Box::new(std::iter::once(state)),
+///             Expansion::Forked(mut states) => match states.next() {
+///                 Some(state) => self.search(state),
+///                 None => Box::new(std::iter::empty()),
+///             },
+///         }
+///     }
+/// }
+///
+/// let x = var();
+/// let goal: Goal<I32> = any![unify(x, 1), unify(x, 2)];
+/// let result: Vec<_> = goal.query_with(FirstBranch, x).collect();
+/// assert_eq!(result, vec![1])
+/// ```
+pub trait SearchStrategy<'a, D: Domain<'a> + 'a> {
+    /// Set up a fresh [`State`] before any goals are applied to it.
+    ///
+    /// The default implementation returns the state unchanged. Wrappers such
+    /// as [`Budgeted`](crate::budget::Budgeted) use this to attach settings
+    /// that must be in place before the first unification.
+    fn prepare(&self, state: State<'a, D>) -> State<'a, D> {
+        state
+    }
+
+    /// Return an iterator of every state without pending forks that can be
+    /// derived from the given [`State`].
+    fn search(&self, state: State<'a, D>) -> StateIter<'a, D>;
+}
+
+/// The result of [expanding](State::expand()) the next pending
+/// [fork](crate::state::Fork) of a [`State`].
+pub enum Expansion<'a, D: Domain<'a> + 'a> {
+    /// The state had a pending fork, which yielded these alternate states.
+    Forked(StateIter<'a, D>),
+    /// The state had no pending forks left.
+    Settled(State<'a, D>),
+}
+
+/// Explore each branch completely before moving on to the next.
+///
+/// This is the default strategy, used by
+/// [`.iter_resolved()`](crate::IterResolved::iter_resolved()) and
+/// [`.query()`](crate::Query::query()). It is usually the fastest, but a
+/// branch with infinitely many alternatives will prevent any that come after it
+/// from ever being reached.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DepthFirst;
+
+impl<'a, D: Domain<'a> + 'a> SearchStrategy<'a, D> for DepthFirst {
+    fn search(&self, state: State<'a, D>) -> StateIter<'a, D> {
+        depth_first(state)
+    }
+}
+
+fn depth_first<'a, D: Domain<'a> + 'a>(state: State<'a, D>) -> StateIter<'a, D> {
+    match state.expand() {
+        Expansion::Settled(state) => Box::new(once(state)),
+        Expansion::Forked(states) => Box::new(states.flat_map(depth_first)),
+    }
+}
+
+/// Explore every branch at one level before moving deeper.
+///
+/// Results are found in order of the number of forks that had to be expanded
+/// to reach them. This guarantees that a shallow result will be found, but the
+/// entire frontier of pending states must be held in memory.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BreadthFirst;
+
+impl<'a, D: Domain<'a> + 'a> SearchStrategy<'a, D> for BreadthFirst {
+    fn search(&self, state: State<'a, D>) -> StateIter<'a, D> {
+        Box::new(BreadthFirstIter {
+            queue: once(state).collect(),
+        })
+    }
+}
+
+struct BreadthFirstIter<'a, D: Domain<'a> + 'a> {
+    queue: VecDeque<State<'a, D>>,
+}
+
+impl<'a, D: Domain<'a> + 'a> Iterator for BreadthFirstIter<'a, D> {
+    type Item = State<'a, D>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(state) = self.queue.pop_front() {
+            match state.expand() {
+                Expansion::Settled(state) => return Some(state),
+                Expansion::Forked(states) => self.queue.extend(states),
+            }
+        }
+        None
+    }
+}
+
+/// Repeat a depth limited depth first search with increasing limits.
+///
+/// Results are found in the same order as with [`BreadthFirst`], while only
+/// holding a single path in memory at a time. The cost is that the shallower
+/// levels are expanded again on each iteration.
+///
+/// # Example:
+/// ```
+/// use canrun::{Goal, any, unify, var};
+/// use canrun::search::IterativeDeepening;
+/// use canrun::example::I32;
+///
+/// let x = var();
+/// let goal: Goal<I32> = any![any![unify(x, 1), unify(x, 2)], unify(x, 3)];
+/// let result: Vec<_> = goal.query_with(IterativeDeepening::new(), x).collect();
+/// assert_eq!(result, vec![3, 1, 2])
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct IterativeDeepening {
+    step: usize,
+}
+
+impl IterativeDeepening {
+    /// Increase the depth limit by one fork for each iteration.
+    pub fn new() -> Self {
+        IterativeDeepening { step: 1 }
+    }
+
+    /// Increase the depth limit by `step` forks for each iteration.
+    ///
+    /// Larger steps mean fewer repeated expansions, at the cost of results
+    /// within each step no longer being found in order of depth.
+    ///
+    /// # Panics
+    /// If `step` is zero.
+    pub fn with_step(step: usize) -> Self {
+        assert!(
+            step > 0,
+            "IterativeDeepening step must be greater than zero"
+        );
+        IterativeDeepening { step }
+    }
+}
+
+impl Default for IterativeDeepening {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, D: Domain<'a> + 'a> SearchStrategy<'a, D> for IterativeDeepening {
+    fn search(&self, state: State<'a, D>) -> StateIter<'a, D> {
+        Box::new(IterativeDeepeningIter {
+            stack: vec![(0, Box::new(once(state.clone())))],
+            root: state,
+            step: self.step,
+            limit: self.step,
+            previous: None,
+            cut_off: false,
+        })
+    }
+}
+
+struct IterativeDeepeningIter<'a, D: Domain<'a> + 'a> {
+    root: State<'a, D>,
+    step: usize,
+    limit: usize,
+    // The limit of the previous iteration. Anything at or above this depth has
+    // already been yielded.
+    previous: Option<usize>,
+    // Whether any state was left unexpanded because it hit the limit.
+    cut_off: bool,
+    stack: Vec<(usize, StateIter<'a, D>)>,
+}
+
+impl<'a, D: Domain<'a> + 'a> Iterator for IterativeDeepeningIter<'a, D> {
+    type Item = State<'a, D>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while let Some((depth, states)) = self.stack.last_mut() {
+                let depth = *depth;
+                let state = match states.next() {
+                    Some(state) => state,
+                    None => {
+                        self.stack.pop();
+                        continue;
+                    }
+                };
+                if depth >= self.limit && !state.forks.is_empty() {
+                    self.cut_off = true;
+                    continue;
+                }
+                match state.expand() {
+                    Expansion::Settled(state) => match self.previous {
+                        Some(previous) if depth <= previous => continue,
+                        _ => return Some(state),
+                    },
+                    Expansion::Forked(states) => self.stack.push((depth + 1, states)),
+                }
+            }
+            if !self.cut_off {
+                return None;
+            }
+            self.cut_off = false;
+            self.previous = Some(self.limit);
+            self.limit += self.step;
+            self.stack.push((0, Box::new(once(self.root.clone()))));
+        }
+    }
+}
+
+/// Fairly interleave the branches of every pending fork.
+///
+/// Each stream of forked states is advanced by one state at a time in round
+/// robin order, in the style of miniKanren's interleaving streams. A branch
+/// with infinitely many (or infinitely deep) alternatives cannot starve its
+/// siblings the way it would with [`DepthFirst`].
+///
+/// # Example:
+/// ```
+/// use canrun::{Goal, any, either, lazy, unify, var, LVar};
+/// use canrun::search::Interleave;
+/// use canrun::example::I32;
+///
+/// fn forever<'a>(x: LVar<i32>) -> Goal<'a, I32> {
+///     lazy(move || either(forever(x), unify(x, 0)))
+/// }
+///
+/// let x = var();
+/// let goal: Goal<I32> = any![forever(x), unify(x, 1)];
+/// let result: Vec<_> = goal.query_with(Interleave, x).take(3).collect();
+/// assert!(result.contains(&1));
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Interleave;
+
+impl<'a, D: Domain<'a> + 'a> SearchStrategy<'a, D> for Interleave {
+    fn search(&self, state: State<'a, D>) -> StateIter<'a, D> {
+        let root: StateIter<'a, D> = Box::new(once(state));
+        Box::new(InterleaveIter {
+            streams: once(root).collect(),
+        })
+    }
+}
+
+struct InterleaveIter<'a, D: Domain<'a> + 'a> {
+    streams: VecDeque<StateIter<'a, D>>,
+}
+
+impl<'a, D: Domain<'a> + 'a> Iterator for InterleaveIter<'a, D> {
+    type Item = State<'a, D>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(mut stream) = self.streams.pop_front() {
+            if let Some(state) = stream.next() {
+                self.streams.push_back(stream);
+                match state.expand() {
+                    Expansion::Forked(states) => self.streams.push_back(states),
+                    Expansion::Settled(state) => return Some(state),
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BreadthFirst, DepthFirst, Interleave, IterativeDeepening, SearchStrategy};
+    use crate as canrun;
+    use crate::example::I32;
+    use crate::{any, either, lazy, unify, var, Goal, LVar};
+
+    fn forever<'a>(x: LVar<i32>) -> Goal<'a, I32> {
+        lazy(move || either(forever(x), unify(x, 0)))
+    }
+
+    fn nested<'a>(x: LVar<i32>) -> Goal<'a, I32> {
+        any![
+            any![unify(x, 1), any![unify(x, 2), unify(x, 3)]],
+            unify(x, 4),
+            any![unify(x, 5), unify(x, 6)],
+        ]
+    }
+
+    fn results_with<S: SearchStrategy<'static, I32>>(strategy: S) -> Vec<i32> {
+        let x = var();
+        nested(x).query_with(strategy, x).collect()
+    }
+
+    #[test]
+    fn depth_first_order() {
+        assert_eq!(results_with(DepthFirst), vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn breadth_first_order() {
+        assert_eq!(results_with(BreadthFirst), vec![4, 1, 5, 6, 2, 3]);
+    }
+
+    #[test]
+    fn iterative_deepening_order() {
+        assert_eq!(
+            results_with(IterativeDeepening::new()),
+            vec![4, 1, 5, 6, 2, 3]
+        );
+    }
+
+    #[test]
+    fn iterative_deepening_with_step() {
+        let mut results = results_with(IterativeDeepening::with_step(2));
+        results.sort();
+        assert_eq!(results, vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn interleave_finds_everything() {
+        let mut results = results_with(Interleave);
+        results.sort();
+        assert_eq!(results, vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn fair_strategies_escape_infinite_branches() {
+        fn finds_1<S: SearchStrategy<'static, I32>>(strategy: S) -> bool {
+            let x = var();
+            let goal: Goal<I32> = any![forever(x), unify(x, 1)];
+            goal.query_with(strategy, x).take(10).any(|x| x == 1)
+        }
+        assert!(finds_1(BreadthFirst));
+        assert!(finds_1(IterativeDeepening::new()));
+        assert!(finds_1(Interleave));
+    }
+}
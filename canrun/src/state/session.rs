@@ -0,0 +1,229 @@
+//! Ask many "what if" questions on top of a shared base goal.
+//!
+//! A [`Session`] applies a base [goal](crate::goals) once, then lets you stack
+//! further goals on top of it with [`.assume()`](Session::assume()) and take
+//! them off again with [`.retract()`](Session::retract()). Each level can be
+//! [queried](crate::Query) without re-running the levels below it.
+//!
+//! Because a [`State`] is built on persistent data structures, every
+//! checkpoint shares almost all of its memory with the level below it.
+//!
+//! # Example:
+//! ```
+//! use canrun::{Goal, Query, any, unify, var};
+//! use canrun::session::Session;
+//! use canrun::example::I32;
+//!
+//! let x = var();
+//! let base: Goal<I32> = any![unify(x, 1), unify(x, 2), unify(x, 3)];
+//! let mut session = Session::new(base);
+//!
+//! session.assume(unify(x, 2));
+//! assert_eq!(session.query(x).collect::<Vec<_>>(), vec![2]);
+//!
+//! session.retract();
+//! assert_eq!(session.query(x).collect::<Vec<_>>(), vec![1, 2, 3]);
+//! ```
+
+use super::iter_resolved::{IterResolved, ResidualStateIter, ResolvedStateIter};
+use super::search::SearchStrategy;
+use super::State;
+use crate::domains::Domain;
+use crate::goals::Goal;
+use std::fmt;
+
+/// A base [`State`] with a stack of assumptions on top of it.
+///
+/// See the [module level documentation](crate::session) for more information.
+/// Queries are made through a reference, so the session can keep being used
+/// afterwards:
+/// ```
+/// use canrun::{Goal, IterResolved, Query, unify, var};
+/// use canrun::session::Session;
+/// use canrun::example::I32;
+///
+/// let x = var();
+/// let session: Session<I32> = Session::new(unify(x, 1));
+/// assert_eq!((&session).iter_resolved().count(), 1);
+/// assert_eq!(session.query(x).collect::<Vec<_>>(), vec![1]);
+/// ```
+#[derive(Clone)]
+pub struct Session<'a, D: Domain<'a> + 'a> {
+    // The base state is always at the bottom. A level is `None` once a goal
+    // has failed, and stays that way until it is retracted.
+    levels: Vec<Option<State<'a, D>>>,
+}
+
+impl<'a, D: Domain<'a> + 'a> Session<'a, D> {
+    /// Start a session by applying a base goal to a new [`State`].
+    pub fn new(goal: Goal<'a, D>) -> Self {
+        Session::from_state(goal.apply(State::new()))
+    }
+
+    /// Start a session from a state that has already been set up.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{State, val, var};
+    /// use canrun::session::Session;
+    /// use canrun::example::I32;
+    ///
+    /// let x = var();
+    /// let state: Option<State<I32>> = State::new().unify(&val!(x), &val!(1));
+    /// let session = Session::from_state(state);
+    /// ```
+    pub fn from_state(state: Option<State<'a, D>>) -> Self {
+        Session {
+            levels: vec![state],
+        }
+    }
+
+    /// Push a checkpoint and apply a goal on top of the current level.
+    ///
+    /// Returns `false` if the goal failed outright. Pending
+    /// [forks](crate::state::State::fork()) are not explored, so `true` does
+    /// not guarantee that there are any results. The checkpoint is pushed
+    /// either way, so every `assume` can be paired with a
+    /// [`.retract()`](Session::retract()).
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, unify, var};
+    /// use canrun::session::Session;
+    /// use canrun::example::I32;
+    ///
+    /// let x = var();
+    /// let mut session: Session<I32> = Session::new(unify(x, 1));
+    /// assert!(!session.assume(unify(x, 2)));
+    /// assert!(!session.assume(unify(x, 1)));
+    /// assert_eq!(session.depth(), 2);
+    /// ```
+    pub fn assume(&mut self, goal: Goal<'a, D>) -> bool {
+        let state = self.current().clone().and_then(|state| goal.apply(state));
+        let succeeded = state.is_some();
+        self.levels.push(state);
+        succeeded
+    }
+
+    /// Pop back to the checkpoint made by the most recent
+    /// [`.assume()`](Session::assume()).
+    ///
+    /// Returns `false` if there is nothing left to retract. The base goal
+    /// can't be retracted.
+    ///
+    /// # Example:
+    /// ```
+    /// use canrun::{Goal, unify, var};
+    /// use canrun::session::Session;
+    /// use canrun::example::I32;
+    ///
+    /// let x = var();
+    /// let mut session: Session<I32> = Session::new(unify(x, 1));
+    /// session.assume(unify(x, 2));
+    /// assert!(session.retract());
+    /// assert!(!session.retract());
+    /// ```
+    pub fn retract(&mut self) -> bool {
+        if self.levels.len() > 1 {
+            self.levels.pop();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// The number of assumptions currently in effect.
+    pub fn depth(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    /// The state at the current level, or `None` if it has failed.
+    pub fn state(&self) -> Option<&State<'a, D>> {
+        self.current().as_ref()
+    }
+
+    fn current(&self) -> &Option<State<'a, D>> {
+        self.levels.last().expect("the base level is never removed")
+    }
+}
+
+impl<'a, D: Domain<'a> + 'a> IterResolved<'a, D> for &Session<'a, D> {
+    fn iter_resolved_with<S>(self, strategy: S) -> ResolvedStateIter<'a, D>
+    where
+        S: SearchStrategy<'a, D>,
+    {
+        self.current().clone().iter_resolved_with(strategy)
+    }
+
+    fn iter_residual_with<S>(self, strategy: S) -> ResidualStateIter<'a, D>
+    where
+        S: SearchStrategy<'a, D>,
+    {
+        self.current().clone().iter_residual_with(strategy)
+    }
+}
+
+impl<'a, D: Domain<'a> + 'a> fmt::Debug for Session<'a, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Session")
+            .field("depth", &self.depth())
+            .field("failed", &self.current().is_none())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Session;
+    use crate as canrun;
+    use crate::example::I32;
+    use crate::{any, lt, unify, var, Goal, Query};
+
+    fn results(session: &Session<'static, I32>, x: crate::LVar<i32>) -> Vec<i32> {
+        session.query(x).collect()
+    }
+
+    #[test]
+    fn nested_assumptions() {
+        let (x, y) = (var(), var());
+        let base: Goal<I32> = any![unify(x, 1), unify(x, 2), unify(x, 3)];
+        let mut session = Session::new(base);
+
+        assert!(session.assume(lt(x, y)));
+        assert!(session.assume(unify(y, 3)));
+        assert_eq!(results(&session, x), vec![1, 2]);
+        assert!(session.assume(unify(x, 2)));
+        assert_eq!(results(&session, x), vec![2]);
+
+        session.retract();
+        session.retract();
+        // `x < y` is still waiting on `y`, so nothing resolves.
+        assert!(results(&session, x).is_empty());
+        session.retract();
+        assert_eq!(results(&session, x), vec![1, 2, 3]);
+        assert_eq!(session.depth(), 0);
+    }
+
+    #[test]
+    fn failed_levels_can_be_retracted() {
+        let x = var();
+        let mut session: Session<I32> = Session::new(unify(x, 1));
+        assert!(!session.assume(unify(x, 2)));
+        assert!(session.state().is_none());
+        assert!(!session.assume(Goal::succeed()));
+        assert!(results(&session, x).is_empty());
+
+        session.retract();
+        session.retract();
+        assert_eq!(results(&session, x), vec![1]);
+    }
+
+    #[test]
+    fn failed_base() {
+        let mut session: Session<I32> = Session::new(unify(1, 2));
+        assert!(!session.assume(Goal::succeed()));
+        assert!(session.retract());
+        assert!(!session.retract());
+        assert!(session.state().is_none());
+    }
+}
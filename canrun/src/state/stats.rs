@@ -0,0 +1,252 @@
+//! Count the work a search does.
+//!
+//! A [`QueryStats`] is an [`Observer`] that tallies the [events](Event) of a
+//! search instead of logging them. Comparing the numbers for two formulations
+//! of the same problem shows which one explores less, and asserting on them
+//! in a test catches a regression before it shows up in a benchmark.
+//!
+//! # Example:
+//! ```
+//! use canrun::{Goal, any, unify, var};
+//! use canrun::stats::QueryStats;
+//! use canrun::example::I32;
+//!
+//! let x = var();
+//! let stats = QueryStats::new();
+//! let goal: Goal<I32> = any![unify(x, 1), unify(x, 2), unify(x, 3)];
+//! let results: Vec<_> = goal.with_stats(&stats).query(x).collect();
+//! assert_eq!(results, vec![1, 2, 3]);
+//! assert_eq!(stats.forks_expanded(), 1);
+//! assert_eq!(stats.solutions(), 3);
+//! ```
+
+use super::observer::{Event, Observer};
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Counters filled in by a search, through [`Goal::with_stats()`] or by
+/// attaching it as an [`Observer`].
+///
+/// Stats are cheap to clone, and all clones share the same counters. They can
+/// be attached alongside other observers, such as a
+/// [`LogObserver`](crate::observer::LogObserver).
+///
+/// See the [module level documentation](crate::stats) for more information.
+///
+/// [`Goal::with_stats()`]: crate::goals::Goal::with_stats()
+#[derive(Clone, Default)]
+pub struct QueryStats {
+    counts: Arc<Counts>,
+}
+
+#[derive(Default)]
+struct Counts {
+    states_cloned: AtomicUsize,
+    forks_pushed: AtomicUsize,
+    forks_expanded: AtomicUsize,
+    peak_pending_forks: AtomicUsize,
+    unifications: AtomicUsize,
+    failed_unifications: AtomicUsize,
+    constraint_attempts: AtomicUsize,
+    constraint_rewatches: AtomicUsize,
+    redundant_constraints: AtomicUsize,
+    solutions: AtomicUsize,
+    dead_ends: AtomicUsize,
+}
+
+fn bump(counter: &AtomicUsize) {
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+impl QueryStats {
+    /// Create a new set of stats with every counter at zero.
+    pub fn new() -> Self {
+        QueryStats::default()
+    }
+
+    /// The number of times a [`State`](crate::State) was cloned.
+    pub fn states_cloned(&self) -> usize {
+        self.counts.states_cloned.load(Ordering::Relaxed)
+    }
+
+    /// The number of [forks](crate::State::fork()) added to a state.
+    pub fn forks_pushed(&self) -> usize {
+        self.counts.forks_pushed.load(Ordering::Relaxed)
+    }
+
+    /// The number of forks that were expanded into alternate states.
+    pub fn forks_expanded(&self) -> usize {
+        self.counts.forks_expanded.load(Ordering::Relaxed)
+    }
+
+    /// The most forks that were waiting in any one state at the same time.
+    pub fn peak_pending_forks(&self) -> usize {
+        self.counts.peak_pending_forks.load(Ordering::Relaxed)
+    }
+
+    /// The number of [unifications](crate::State::unify()) attempted,
+    /// including those of values nested inside a structure.
+    pub fn unifications(&self) -> usize {
+        self.counts.unifications.load(Ordering::Relaxed)
+    }
+
+    /// The number of unifications that failed.
+    pub fn failed_unifications(&self) -> usize {
+        self.counts.failed_unifications.load(Ordering::Relaxed)
+    }
+
+    /// The number of times a [constraint](crate::State::constrain()) was
+    /// attempted, whether it could be checked or had to wait.
+    pub fn constraint_attempts(&self) -> usize {
+        self.counts.constraint_attempts.load(Ordering::Relaxed)
+    }
+
+    /// The number of times a constraint was woken by a binding, but still
+    /// had to wait and was registered again.
+    pub fn constraint_rewatches(&self) -> usize {
+        self.counts.constraint_rewatches.load(Ordering::Relaxed)
+    }
+
+    /// The number of times a constraint had to wait, but was dropped since
+    /// an identical or subsuming one was already waiting.
+    pub fn redundant_constraints(&self) -> usize {
+        self.counts.redundant_constraints.load(Ordering::Relaxed)
+    }
+
+    /// The number of fully resolved states that were found.
+    pub fn solutions(&self) -> usize {
+        self.counts.solutions.load(Ordering::Relaxed)
+    }
+
+    /// The number of branches that ended without a solution.
+    ///
+    /// See [`Event::DeadEnd`] for what is counted.
+    pub fn dead_ends(&self) -> usize {
+        self.counts.dead_ends.load(Ordering::Relaxed)
+    }
+}
+
+impl Observer for QueryStats {
+    fn observe(&self, _depth: usize, event: &Event<'_>) {
+        let counts = &self.counts;
+        match event {
+            Event::Unify { succeeded, .. } => {
+                bump(&counts.unifications);
+                if !succeeded {
+                    bump(&counts.failed_unifications);
+                }
+            }
+            Event::Constraint { .. } => bump(&counts.constraint_attempts),
+            Event::Watch { woken, .. } => {
+                bump(&counts.constraint_attempts);
+                if *woken {
+                    bump(&counts.constraint_rewatches);
+                }
+            }
+            Event::Redundant { .. } => {
+                bump(&counts.constraint_attempts);
+                bump(&counts.redundant_constraints);
+            }
+            Event::ForkPushed { pending, .. } => {
+                bump(&counts.forks_pushed);
+                counts
+                    .peak_pending_forks
+                    .fetch_max(*pending, Ordering::Relaxed);
+            }
+            Event::ForkExpanded { .. } => bump(&counts.forks_expanded),
+            Event::Solution => bump(&counts.solutions),
+            Event::DeadEnd => bump(&counts.dead_ends),
+            Event::StateCloned => bump(&counts.states_cloned),
+        }
+    }
+}
+
+impl fmt::Debug for QueryStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueryStats")
+            .field("states_cloned", &self.states_cloned())
+            .field("forks_pushed", &self.forks_pushed())
+            .field("forks_expanded", &self.forks_expanded())
+            .field("peak_pending_forks", &self.peak_pending_forks())
+            .field("unifications", &self.unifications())
+            .field("failed_unifications", &self.failed_unifications())
+            .field("constraint_attempts", &self.constraint_attempts())
+            .field("constraint_rewatches", &self.constraint_rewatches())
+            .field("redundant_constraints", &self.redundant_constraints())
+            .field("solutions", &self.solutions())
+            .field("dead_ends", &self.dead_ends())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QueryStats;
+    use crate as canrun;
+    use crate::example::Collections;
+    use crate::example::I32;
+    use crate::{add, all, any, lt, lvec, unify, var, Goal, IterResolved, LVar};
+
+    fn run(goal: Goal<'static, I32>) -> QueryStats {
+        let stats = QueryStats::new();
+        goal.with_stats(&stats).iter_resolved().count();
+        stats
+    }
+
+    #[test]
+    fn counts_forks_solutions_and_dead_ends() {
+        let x = var();
+        let stats = run(all![
+            any![unify(x, 1), unify(x, 2), unify(x, 3)],
+            any![unify(x, 2), unify(x, 3)],
+        ]);
+        assert_eq!(stats.forks_pushed(), 2);
+        assert_eq!(stats.peak_pending_forks(), 2);
+        assert_eq!(stats.solutions(), 2);
+        // The smaller fork is expanded first, then two of the three
+        // alternatives in the other fork fail in each of its branches.
+        assert_eq!(stats.forks_expanded(), 3);
+        assert_eq!(stats.dead_ends(), 4);
+        assert!(stats.failed_unifications() >= 2);
+        assert!(stats.states_cloned() > 0);
+    }
+
+    #[test]
+    fn counts_constraint_rewatches() {
+        let (x, y, z): (LVar<i32>, LVar<i32>, LVar<i32>) = (var(), var(), var());
+        let stats = run(all![lt(x, y), unify(x, z), unify(z, 1), unify(y, 2)]);
+        assert_eq!(stats.solutions(), 1);
+        assert_eq!(stats.constraint_attempts(), 2);
+        assert_eq!(stats.constraint_rewatches(), 0);
+
+        // With all three unbound, binding `x` isn't enough for `add`.
+        let stats = run(all![add(x, y, z), unify(x, 1), unify(y, 2)]);
+        assert_eq!(stats.solutions(), 1);
+        assert_eq!(stats.constraint_attempts(), 3);
+        assert_eq!(stats.constraint_rewatches(), 1);
+    }
+
+    #[test]
+    fn unresolved_constraints_are_dead_ends() {
+        let (x, y): (LVar<i32>, LVar<i32>) = (var(), var());
+        let stats = run(all![lt(x, y), unify(x, 1)]);
+        assert_eq!(stats.solutions(), 0);
+        assert_eq!(stats.dead_ends(), 1);
+    }
+
+    #[test]
+    fn counts_redundant_constraints() {
+        let x = var();
+        let stats = QueryStats::new();
+        let goal: Goal<Collections> = all![
+            lvec::member(1, x),
+            lvec::member(1, x),
+            unify(x, lvec![1, 2]),
+        ];
+        goal.with_stats(&stats).iter_resolved().count();
+        assert_eq!(stats.solutions(), 1);
+        assert_eq!(stats.redundant_constraints(), 1);
+        assert_eq!(stats.constraint_attempts(), 3);
+    }
+}
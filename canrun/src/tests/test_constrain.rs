@@ -1,22 +1,25 @@
 use crate::domains::DomainType;
-use crate::example::I32;
+use crate::example::{Collections, I32};
 use crate::goals::custom;
+use crate::goals::project::map_1;
 use crate::goals::unify;
 use crate::goals::Goal;
-use crate::state::constraints::{Constraint, ResolveFn, VarWatch};
+use crate::lmap::{lmap, LMap};
+use crate::shared::{Rc, Shareable};
+use crate::state::constraints::{Constraint, ConstraintKey, ResolveFn, VarWatch};
 use crate::state::State;
 use crate::util;
 use crate::value::{
-    val, var, IntoVal, Val,
+    val, var, IntoVal, LVar, Val,
     Val::{Resolved, Var},
 };
 use std::fmt;
 use std::fmt::Debug;
-use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 struct Assert<'a, T: Debug> {
     val: Val<T>,
-    assert: Rc<dyn Fn(&T) -> bool + 'a>,
+    assert: Rc<dyn_fn!(Fn(&T) -> bool + 'a)>,
 }
 
 impl<'a, T: fmt::Debug> fmt::Debug for Assert<'a, T> {
@@ -27,7 +30,7 @@ impl<'a, T: fmt::Debug> fmt::Debug for Assert<'a, T> {
 
 impl<'a, T, D> Constraint<'a, D> for Assert<'a, T>
 where
-    T: Debug + 'a,
+    T: Debug + Shareable + 'a,
     D: DomainType<'a, T>,
 {
     fn attempt(&self, state: &State<'a, D>) -> Result<ResolveFn<'a, D>, VarWatch> {
@@ -46,10 +49,10 @@ where
 
 pub(crate) fn assert<'a, T, V, D, F>(val: V, func: F) -> Rc<dyn Constraint<'a, D> + 'a>
 where
-    T: Debug + 'a,
+    T: Debug + Shareable + 'a,
     V: IntoVal<T> + Clone + 'a,
     D: DomainType<'a, T> + 'a,
-    F: Fn(&T) -> bool + 'a,
+    F: Fn(&T) -> bool + Shareable + 'a,
 {
     Rc::new(Assert {
         val: val.into_val(),
@@ -90,3 +93,145 @@ fn unsatisfied_constrain_fails() {
     util::assert_permutations_resolve_to(goals.clone(), x, vec![]);
     util::assert_permutations_resolve_to(goals, y, vec![]);
 }
+
+#[derive(Debug)]
+struct CountAttempts {
+    a: LVar<i32>,
+    b: LVar<i32>,
+    watch_both: bool,
+    attempts: Rc<AtomicUsize>,
+}
+
+impl<'a> Constraint<'a, I32> for CountAttempts {
+    fn attempt(&self, state: &State<'a, I32>) -> Result<ResolveFn<'a, I32>, VarWatch> {
+        self.attempts.fetch_add(1, Ordering::SeqCst);
+        let a = state.resolve_val(&val!(self.a)).clone();
+        let b = state.resolve_val(&val!(self.b)).clone();
+        match (a, b) {
+            (Resolved(_), Resolved(_)) => Ok(Box::new(Some)),
+            (Var(a), Var(b)) if self.watch_both => Err(VarWatch::both(a, b)),
+            (Var(a), Var(b)) => Err(VarWatch::two(a, b)),
+            (Var(var), _) | (_, Var(var)) => Err(VarWatch::one(var)),
+        }
+    }
+}
+
+// Attempt the constraint against `a` and `b`, then run the steps in order.
+fn count_attempts<F>(watch_both: bool, steps: F) -> usize
+where
+    F: FnOnce(State<'static, I32>, LVar<i32>, LVar<i32>) -> Option<State<'static, I32>>,
+{
+    let (a, b) = (var(), var());
+    let attempts = Rc::new(AtomicUsize::new(0));
+    let state = State::new().constrain(Rc::new(CountAttempts {
+        a,
+        b,
+        watch_both,
+        attempts: attempts.clone(),
+    }));
+    let state = steps(state.unwrap(), a, b).unwrap();
+    assert!(state.pending_constraints().is_empty());
+    attempts.load(Ordering::SeqCst)
+}
+
+#[test]
+fn watching_either_var_wakes_on_each_binding() {
+    let attempts = count_attempts(false, |state, a, b| {
+        state.unify(&val!(a), &val!(1))?.unify(&val!(b), &val!(2))
+    });
+    assert_eq!(attempts, 3);
+}
+
+#[test]
+fn watching_both_vars_waits_for_the_last_binding() {
+    let attempts = count_attempts(true, |state, a, b| {
+        state.unify(&val!(a), &val!(1))?.unify(&val!(b), &val!(2))
+    });
+    assert_eq!(attempts, 2);
+}
+
+#[test]
+fn watching_both_vars_follows_joined_vars() {
+    let attempts = count_attempts(true, |state, a, b| {
+        let c = var();
+        state
+            .unify(&val!(a), &val!(c))?
+            .unify(&val!(b), &val!(2))?
+            .unify(&val!(c), &val!(1))
+    });
+    assert_eq!(attempts, 2);
+
+    let attempts = count_attempts(true, |state, a, b| {
+        state.unify(&val!(a), &val!(b))?.unify(&val!(b), &val!(2))
+    });
+    assert_eq!(attempts, 2);
+}
+
+#[test]
+fn woken_duplicates_are_only_attempted_once() {
+    let (a, b) = (var(), var());
+    let attempts = Rc::new(AtomicUsize::new(0));
+    let constraint = Rc::new(CountAttempts {
+        a,
+        b,
+        watch_both: false,
+        attempts: attempts.clone(),
+    });
+    let state: State<I32> = State::new()
+        .constrain(constraint.clone())
+        .unwrap()
+        .constrain(constraint)
+        .unwrap();
+    assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+    let state = state.unify(&val!(a), &val!(1)).unwrap();
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    state.unify(&val!(b), &val!(2)).unwrap();
+    assert_eq!(attempts.load(Ordering::SeqCst), 4);
+}
+
+#[test]
+fn long_chains_of_constraints_propagate() {
+    let vars: Vec<LVar<i32>> = (0..5000).map(|_| var()).collect();
+    let mut goals: Vec<Goal<I32>> = vars
+        .windows(2)
+        .map(|pair| map_1(pair[0], pair[1], |a| a + 1, |b| b - 1))
+        .collect();
+    goals.push(unify(vars[0], 0));
+    let results: Vec<_> = Goal::all(goals).query(vars[4999]).collect();
+    assert_eq!(results, vec![4999]);
+}
+
+#[test]
+fn constraint_keys_match_through_bindings() {
+    let (x, y): (LVar<i32>, LVar<i32>) = (var(), var());
+    let key = |a: Val<i32>, b: Val<i32>| -> ConstraintKey<I32> {
+        ConstraintKey::new("Pair").with(&a).with(&b)
+    };
+    let state: State<I32> = State::new();
+    assert!(key(val!(x), val!(1)).matches(&key(val!(x), val!(1)), &state));
+    assert!(!key(val!(x), val!(1)).matches(&key(val!(y), val!(1)), &state));
+    assert!(!key(val!(x), val!(1)).matches(&key(val!(1), val!(1)), &state));
+    assert!(!ConstraintKey::<I32>::new("Other")
+        .with(&val!(x))
+        .with(&val!(1))
+        .matches(&key(val!(x), val!(1)), &state));
+
+    let state = state.unify(&val!(x), &val!(1)).unwrap();
+    assert!(key(val!(x), val!(1)).matches(&key(val!(1), val!(1)), &state));
+    assert!(!key(val!(x), val!(1)).matches(&key(val!(2), val!(1)), &state));
+
+    let state = state.unify(&val!(x), &val!(y)).unwrap();
+    assert!(key(val!(x), val!(1)).matches(&key(val!(y), val!(1)), &state));
+}
+
+#[test]
+fn constraint_keys_with_forking_values_do_not_match() {
+    let (x, y): (LVar<i32>, LVar<i32>) = (var(), var());
+    let key = |map: LMap<i32, i32>| -> ConstraintKey<Collections> {
+        ConstraintKey::new("Map").with(&val!(map))
+    };
+    let state: State<Collections> = State::new();
+    assert!(key(lmap! {x => 1}).matches(&key(lmap! {x => 1}), &state));
+    assert!(!key(lmap! {x => 1}).matches(&key(lmap! {y => 1}), &state));
+}
@@ -1,14 +1,13 @@
 use super::super::state::{Fork, IterResolved, State};
 use crate::domains::Domain;
 use crate::example::I32;
+use crate::shared::Rc;
 use crate::value::val;
 use std::fmt;
-use std::rc::Rc;
 
-struct Either<'a, D: Domain<'a>>(
-    Rc<dyn Fn(State<'a, D>) -> Option<State<'a, D>> + 'a>,
-    Rc<dyn Fn(State<'a, D>) -> Option<State<'a, D>> + 'a>,
-);
+type StateFn<'a, D> = dyn_fn!(Fn(State<'a, D>) -> Option<State<'a, D>> + 'a);
+
+struct Either<'a, D: Domain<'a>>(Rc<StateFn<'a, D>>, Rc<StateFn<'a, D>>);
 
 impl<'a, D: Domain<'a>> fmt::Debug for Either<'a, D> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
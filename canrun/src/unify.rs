@@ -1,7 +1,7 @@
 use crate::domains::DomainType;
-use crate::state::State;
+use crate::shared::{Rc, Shareable};
+use crate::state::{OccursCheck, State};
 use std::fmt::Debug;
-use std::rc::Rc;
 
 /// How compatible values are matched with each other.
 ///
@@ -42,7 +42,7 @@ use std::rc::Rc;
 /// TODO: Create a derive macro
 /// ```
 /// use canrun::{State, DomainType, UnifyIn};
-/// use std::rc::Rc;
+/// use canrun::shared::Rc;
 ///
 /// #[derive(PartialEq, Debug)]
 /// struct MyType;
@@ -72,7 +72,7 @@ use std::rc::Rc;
 /// # // I'm pretty sure no one will want to unify this for real :)
 /// # use std::convert::Infallible as SomeForeignType;
 /// use canrun::{State, DomainType, UnifyIn};
-/// use std::rc::Rc;
+/// use canrun::shared::Rc;
 ///
 /// canrun::domain! {
 ///     MyDomain {
@@ -92,11 +92,22 @@ use std::rc::Rc;
 /// }
 /// # fn main() {}
 /// ```
-pub trait UnifyIn<'a, D: DomainType<'a, Self>>: Sized + Debug {
+pub trait UnifyIn<'a, D: DomainType<'a, Self>>: Sized + Debug + Shareable {
     /// Attempt to unify two fully resolved values.
     ///
     /// This function accepts `Rc<T>`s to simplify the borrow checking. The
     /// `Option<_>` allows recursive unification of structures that hold
     /// additional values.
     fn unify_resolved(state: State<'a, D>, a: Rc<Self>, b: Rc<Self>) -> Option<State<'a, D>>;
+
+    /// Pass every [`Val`](crate::value::Val) contained in this value to the
+    /// [`OccursCheck`].
+    ///
+    /// This is only called when the [occurs
+    /// check](crate::state::State::with_occurs_check()) is enabled. The
+    /// default implementation is correct for types that do not contain any
+    /// `Val`s.
+    fn occurs_check(&self, check: &mut OccursCheck<'_, 'a, D>) {
+        let _ = check;
+    }
 }
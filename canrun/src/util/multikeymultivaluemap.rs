@@ -1,4 +1,4 @@
-use im_rc::{HashMap, HashSet};
+use crate::shared::{HashMap, HashSet};
 use std::fmt;
 use std::hash::Hash;
 
@@ -13,11 +13,28 @@ impl<K: Eq + Hash + Clone + fmt::Debug, V: Clone> MKMVMap<K, V> {
     pub(crate) fn is_empty(&self) -> bool {
         self.values.is_empty()
     }
+
+    /// The id the next value added will get.
+    pub(crate) fn next_id(&self) -> usize {
+        self.current_id
+    }
+
+    pub(crate) fn values(&self) -> impl Iterator<Item = &Value<K, V>> {
+        self.values.values()
+    }
+
+    /// Iterate over the values stored under a key, without removing them.
+    pub(crate) fn get<'m>(&'m self, key: &K) -> impl Iterator<Item = &'m Value<K, V>> + 'm {
+        self.keys
+            .get(key)
+            .into_iter()
+            .flat_map(move |ids| ids.iter().filter_map(move |id| self.values.get(id)))
+    }
 }
 
 #[derive(Clone)]
 pub(crate) struct Value<K, V> {
-    id: usize,
+    pub id: usize,
     pub keys: Vec<K>,
     pub value: V,
 }
@@ -57,29 +74,31 @@ impl<K: Eq + Hash + Clone + fmt::Debug, V: Clone> MKMVMap<K, V> {
         self.values = self.values.update(id, Value { id, keys, value });
     }
 
-    pub(crate) fn extract(&mut self, key: &K) -> Option<Vec<V>> {
+    /// Remove every value stored under a key, along with the keys they were
+    /// added with.
+    pub(crate) fn extract(&mut self, key: &K) -> Option<Vec<Value<K, V>>> {
         let (ids, keys) = self.keys.extract(&key)?;
         self.keys = keys;
         let mut values = Vec::new();
         for id in ids {
             if let Some((value, value_map)) = self.values.extract(&id) {
                 self.values = value_map;
-                // This attempts to be "correct" by cleaning up all of the ids
-                // when a value is extracted, but this does mean doing a fair
-                // amount of work every time. In theory we could one not bother
-                // and would only pay a minor cost skipping over the garbage.
-                self.keys = self.keys.alter(
-                    |existing| {
-                        let updated = existing?.without(&value.id);
-                        if updated.is_empty() {
-                            None
-                        } else {
-                            Some(updated)
-                        }
-                    },
-                    key.clone(),
-                );
-                values.push(value.value);
+                // Clean the id out of the other keys the value was stored
+                // under so they don't slowly fill up with stale entries.
+                for other in value.keys.iter().filter(|other| *other != key) {
+                    self.keys = self.keys.alter(
+                        |existing| {
+                            let updated = existing?.without(&value.id);
+                            if updated.is_empty() {
+                                None
+                            } else {
+                                Some(updated)
+                            }
+                        },
+                        other.clone(),
+                    );
+                }
+                values.push(value);
             }
         }
         Some(values)
@@ -111,15 +130,29 @@ mod tests {
     fn empty() {
         let mut map: MKMVMap<usize, usize> = MKMVMap::new();
         let values = map.extract(&1);
-        assert_eq!(values, None);
+        assert!(values.is_none());
     }
 
     #[test]
     fn add_and_extract() {
         let mut map = MKMVMap::new();
         map.add(vec![1, 2], "12");
-        let values = map.extract(&1);
-        assert_eq!(values, Some(vec!["12"]));
+        let values = map.extract(&1).unwrap();
+        assert_eq!(values.len(), 1);
+        assert_eq!(values[0].value, "12");
+        assert_eq!(values[0].keys, vec![1, 2]);
         assert!(map.values.is_empty());
+        assert!(map.keys.is_empty());
+    }
+
+    #[test]
+    fn get_leaves_values_in_place() {
+        let mut map = MKMVMap::new();
+        map.add(vec![1, 2], "12");
+        map.add(vec![2], "2");
+        assert_eq!(map.get(&1).map(|v| v.value).collect::<Vec<_>>(), vec!["12"]);
+        assert_eq!(map.get(&2).count(), 2);
+        assert_eq!(map.get(&3).count(), 0);
+        assert_eq!(map.values().count(), 2);
     }
 }
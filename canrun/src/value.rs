@@ -7,16 +7,17 @@
 mod into_val;
 mod lvar;
 
-pub use crate::reify::ReifyIn;
+pub use crate::reify::{ReifyFresh, ReifyIn};
+use crate::shared::Rc;
 pub use into_val::IntoVal;
-pub(super) use lvar::LVarId;
+pub(super) use lvar::{next_id, LVarId};
 pub use lvar::{var, LVar};
 use std::fmt;
 use std::fmt::Debug;
 use std::hash::{Hash, Hasher};
-use std::rc::Rc;
 
 /// The possible states a value can be in.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Val<T: Debug + ?Sized> {
     /// A [logical variable](LVar).
     Var(LVar<T>),
@@ -1,6 +1,6 @@
 use super::{LVar, Val};
+use crate::shared::Rc;
 use std::fmt::Debug;
-use std::rc::Rc;
 
 /// Helper for converting into [`Val<T>`](crate::value::Val).
 ///
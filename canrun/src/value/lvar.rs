@@ -5,11 +5,18 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 
 pub(in super::super) type LVarId = usize;
 
+static COUNTER: AtomicUsize = AtomicUsize::new(1);
+
 fn get_id() -> LVarId {
-    static COUNTER: AtomicUsize = AtomicUsize::new(1);
     COUNTER.fetch_add(1, Ordering::Relaxed)
 }
 
+/// The id the next new variable will get. Every variable that already exists
+/// has a smaller id.
+pub(crate) fn next_id() -> LVarId {
+    COUNTER.load(Ordering::Relaxed)
+}
+
 /// A logical variable that represents a potential value of type `T`.
 ///
 /// They are typically created with the [`var()`](crate::value::var) function.
@@ -26,7 +33,7 @@ fn get_id() -> LVarId {
 #[derive(Default)]
 pub struct LVar<T: ?Sized> {
     pub(in super::super) id: LVarId,
-    label: Option<&'static str>,
+    pub(in super::super) label: Option<&'static str>,
     t: PhantomData<T>,
 }
 
@@ -129,6 +136,42 @@ impl<T> Clone for LVar<T> {
 }
 impl<T> Copy for LVar<T> {}
 
+/// Only the id is serialized. Labels are not restored.
+#[cfg(feature = "serde")]
+impl<T: ?Sized> serde::Serialize for LVar<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.id as u64)
+    }
+}
+
+/// Deserializing an `LVar` makes sure that any created afterwards with
+/// [`LVar::new()`] will have a different id, even in a new process.
+///
+/// Variables that were already created before the snapshot was loaded are
+/// not checked, and may share an id with a restored one. Load snapshots
+/// before creating any variables that will be used alongside them.
+///
+/// Ids that don't fit in a `usize`, or leave no room for another variable
+/// after them, are rejected.
+#[cfg(feature = "serde")]
+impl<'de, T: ?Sized> serde::Deserialize<'de> for LVar<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        use std::convert::TryFrom;
+        let id = <u64 as serde::Deserialize>::deserialize(deserializer)?;
+        let id = LVarId::try_from(id).map_err(|_| D::Error::custom("LVar id out of range"))?;
+        let next = id
+            .checked_add(1)
+            .ok_or_else(|| D::Error::custom("LVar id out of range"))?;
+        COUNTER.fetch_max(next, Ordering::Relaxed);
+        Ok(LVar {
+            id,
+            label: None,
+            t: PhantomData,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::LVar;
@@ -154,4 +197,20 @@ mod tests {
             }
         );
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialized_ids_are_not_reused() {
+        let x: LVar<()> = serde_json::from_str("1000000").unwrap();
+        assert_eq!(x.id, 1_000_000);
+        assert!(LVar::<()>::new().id > x.id);
+        assert_eq!(serde_json::to_string(&x).unwrap(), "1000000");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn out_of_range_ids_are_rejected() {
+        let max = u64::MAX.to_string();
+        assert!(serde_json::from_str::<LVar<()>>(&max).is_err());
+    }
 }
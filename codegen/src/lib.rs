@@ -7,6 +7,7 @@ use syn::{parse_macro_input, Result, Token};
 
 struct DomainDef {
     canrun_internal: bool,
+    domain_attrs: Vec<syn::Attribute>,
     domain_visibility: syn::Visibility,
     domain_name: syn::Ident,
     domain_types: Vec<syn::Type>,
@@ -14,6 +15,8 @@ struct DomainDef {
 
 impl Parse for DomainDef {
     fn parse(input: ParseStream) -> Result<Self> {
+        let domain_attrs = input.call(syn::Attribute::parse_outer)?;
+
         let domain_visibility = input.parse()?;
 
         let domain_name: syn::Ident = input.parse()?;
@@ -27,6 +30,7 @@ impl Parse for DomainDef {
 
         Ok(DomainDef {
             canrun_internal: false,
+            domain_attrs,
             domain_visibility,
             domain_name,
             domain_types,
@@ -38,6 +42,7 @@ impl quote::ToTokens for DomainDef {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
         let DomainDef {
             canrun_internal,
+            domain_attrs,
             domain_visibility,
             domain_name,
             domain_types,
@@ -59,6 +64,7 @@ impl quote::ToTokens for DomainDef {
             #[doc="A custom Domain generated by the domain! macro."]
             #[doc="TODO: Figure out how to interpolate something useful here"]
             #[derive(std::fmt::Debug)]
+            #(#domain_attrs)*
             #domain_visibility struct #domain_name {
                 #(#fields: #canrun_mod::domains::DomainValues<#domain_types>),*
             }
@@ -85,6 +91,16 @@ impl quote::ToTokens for DomainDef {
                         _ => None, // This should only happen if a DomainVal constructor allows two values with different types.
                     }
                 }
+                fn bindings(&self) -> Vec<(Self::Value, Self::Value)> {
+                    use #canrun_mod::value::Val;
+                    let mut bindings = Vec::new();
+                    #(
+                        for (var, val) in self.#fields.iter() {
+                            bindings.push((#value_name::#variants(Val::Var(*var)), #value_name::#variants(val.clone())));
+                        }
+                    )*
+                    bindings
+                }
             }
 
             #(
@@ -1,10 +1,14 @@
 use canrun_examples::zebra::zebra;
+#[cfg(feature = "parallel")]
+use canrun_examples::zebra::zebra_parallel;
 use criterion::{criterion_group, criterion_main, Criterion};
 
 pub fn zebra_benchmark(c: &mut Criterion) {
     let mut group = c.benchmark_group("zebra");
     group.sample_size(10);
     group.bench_function("zebra", |b| b.iter(|| zebra()));
+    #[cfg(feature = "parallel")]
+    group.bench_function("zebra_parallel", |b| b.iter(zebra_parallel));
     group.finish();
 }
 
@@ -35,7 +35,7 @@ fn next_to(a: &LHouse, b: &LHouse, houses: &LVec<LHouse>) -> Goal<'static, Zebra
     either(on_right(a, b, houses), on_right(b, a, houses))
 }
 
-pub fn zebra() -> Option<Vec<House>> {
+fn puzzle() -> (LVec<LHouse>, Goal<'static, Zebra>) {
     let houses: LVec<LHouse> = lvec![
         ltup!("norwegian", var(), var(), var(), var()),
         var(),
@@ -78,9 +78,21 @@ pub fn zebra() -> Option<Vec<House>> {
         member(ltup!(var(), var(), "water", var(), var()), &houses),
         member(ltup!(var(), var(), var(), "zebra", var()), &houses),
     ];
+    (houses, goal)
+}
+
+pub fn zebra() -> Option<Vec<House>> {
+    let (houses, goal) = puzzle();
     goal.query(houses).nth(0)
 }
 
+#[cfg(feature = "parallel")]
+pub fn zebra_parallel() -> Option<Vec<House>> {
+    let (houses, goal) = puzzle();
+    goal.query_parallel(canrun::parallel::Order::Completion, houses)
+        .next()
+}
+
 #[test]
 fn test_zebra() {
     assert_eq!(
@@ -94,3 +106,9 @@ fn test_zebra() {
         ])
     )
 }
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_zebra_parallel() {
+    assert_eq!(zebra_parallel(), zebra());
+}